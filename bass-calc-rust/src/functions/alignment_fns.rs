@@ -0,0 +1,383 @@
+//! Classic alignments of the passive radiator system, after Hurlburt
+//!
+//! An alignment fixes the shape of the denominator of the response, a 4th order high pass. The
+//! driver and the passive radiator's compliance `Cmp` and losses are held, and the box compliance
+//! `Cab` and the radiator mass `Mmp` are fitted so that the system's denominator, normalised to its
+//! natural frequency, matches the alignment's. QB3 and C4 are families, so their member is fitted
+//! too. A driver that can't reach the alignment gets the closest system instead
+
+use std::f64::consts::PI;
+use std::fmt;
+use parameters::Parameters;
+use functions::Radiator;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Alignment {
+    /// Butterworth, maximally flat
+    B4,
+    /// Quasi-Butterworth 3rd order, flat with a slower roll off
+    QB3,
+    /// Chebyshev, equal ripple in the passband
+    C4,
+}
+
+impl Alignment {
+    pub const ALL: [Alignment; 3] = [Alignment::B4, Alignment::QB3, Alignment::C4];
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Alignment::B4 => "B4",
+            Alignment::QB3 => "QB3",
+            Alignment::C4 => "C4",
+        }
+    }
+
+    // Whether the alignment is a family with a member to fit
+    fn is_family(&self) -> bool {
+        *self != Alignment::B4
+    }
+
+    /// Normalised coefficients of s^3, s^2 and s of the alignment's denominator, for the family
+    /// member `k`. That is B for QB3 and the ripple factor ε for C4
+    pub fn target(&self, k: f64) -> [f64; 3] {
+        match *self {
+            Alignment::B4 => chebyshev(0.0),
+            Alignment::QB3 => quasi_butterworth(k.abs()),
+            Alignment::C4 => chebyshev(k.abs()),
+        }
+    }
+}
+
+/// Box and passive radiator that realise an alignment, in SI base units
+pub struct AlignmentFit {
+    pub alignment: Alignment,
+    pub Cab: f64,
+    pub Vb: f64,
+    pub Mmp: f64,
+    pub Fp: f64,
+    pub Fb: f64,
+    /// Family member, as for `Alignment::target`
+    pub k: f64,
+    /// RMS difference of the log normalised coefficients from the alignment's. Near zero when the
+    /// alignment is reached
+    pub error: f64,
+}
+
+/// Why an alignment couldn't be fitted
+#[derive(Debug, PartialEq)]
+pub enum AlignmentError {
+    /// The response isn't finite near the current parameters, as with no driver losses when `Rms`
+    /// is 0
+    NotFinite(Alignment),
+}
+
+impl fmt::Display for AlignmentError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AlignmentError::NotFinite(alignment) => {
+                write!(f, "{} cannot be fitted, the response is not finite for these parameters", alignment.name())
+            },
+        }
+    }
+}
+
+impl AlignmentFit {
+    /// Set the box and passive radiator of `params` to the fitted ones
+    pub fn apply(&self, params: &Parameters) {
+        params.set(&params.Cab, self.Cab);
+        params.set(&params.Mmp, self.Mmp);
+    }
+}
+
+// Coefficients of s^3, s^2 and s of a 4th order polynomial after scaling s to make it monic with
+// a constant term of 1
+fn normalised(p: &[f64]) -> [f64; 3] {
+    let w = (p[4] / p[0]).powf(0.25);
+    [p[1] / (p[0] * w), p[2] / (p[0] * w.powi(2)), p[3] / (p[0] * w.powi(3))]
+}
+
+// Chebyshev high pass with ripple factor `ε`. Its poles are the reciprocals of the low pass
+// poles, so the coefficients are reversed. No ripple is Butterworth
+fn chebyshev(ε: f64) -> [f64; 3] {
+    let v = if ε > 0.0 { (1.0 / ε).asinh() / 4.0 } else { 0.0 };
+    let mut p = vec![1.0];
+    for k in 0 .. 2 {
+        let θ = (2 * k + 1) as f64 * PI / 8.0;
+        // Pair of poles -σ ± jω, shrunk to the unit circle as the ripple vanishes
+        let (σ, ω) = if ε > 0.0 { (v.sinh() * θ.sin(), v.cosh() * θ.cos()) } else { (θ.sin(), θ.cos()) };
+        let section = [1.0, 2.0 * σ, σ * σ + ω * ω];
+        let mut product = vec![0.; p.len() + 2];
+        for (i, a) in p.iter().enumerate() {
+            for (j, b) in section.iter().enumerate() {
+                product[i + j] += a * b;
+            }
+        }
+        p = product;
+    }
+    p.reverse();
+    normalised(&p)
+}
+
+// Small's QB3 family: |H|^2 = w^8 / (w^8 + B^2 w^2 + 1). With a1, a2 and a3 the coefficients of
+// s^3, s^2 and s, the w^6 and w^4 terms vanish for a2 = a1^2 / 2 and a3 = (a1^4 / 4 + 2) / (2 a1),
+// leaving B^2 = a3^2 - a1^2, which grows with a1 from Butterworth at B = 0
+fn quasi_butterworth(B: f64) -> [f64; 3] {
+    let coefficients = |a1: f64| [a1, a1 * a1 / 2.0, (a1.powi(4) / 4.0 + 2.0) / (2.0 * a1)];
+    let excess = |a1: f64| {
+        let [_, _, a3] = coefficients(a1);
+        a3 * a3 - a1 * a1 - B * B
+    };
+    let (mut low, mut high) = (chebyshev(0.0)[0], 10.0);
+    while excess(high) < 0.0 {
+        high *= 2.0;
+    }
+    for _ in 0 .. 100 {
+        let mid = (low + high) / 2.0;
+        if excess(mid) < 0.0 { low = mid } else { high = mid }
+    }
+    coefficients((low + high) / 2.0)
+}
+
+// Log differences of the system's normalised coefficients from the target's, for the unknowns
+// ln Cab, ln Mmp and, for families, the member. Leaves the unknowns set in `params`
+fn residuals(params: &Parameters, alignment: Alignment, u: &[f64]) -> Vec<f64> {
+    params.set(&params.Cab, u[0].exp());
+    params.set(&params.Mmp, u[1].exp());
+    let system = normalised(&Radiator(params).den);
+    let target = alignment.target(if alignment.is_family() { u[2] } else { 0.0 });
+    system.iter().zip(target.iter()).map(|(s, t)| (s / t).ln()).collect()
+}
+
+fn cost(r: &[f64]) -> f64 {
+    let cost = r.iter().map(|x| x * x).sum::<f64>();
+    if cost.is_finite() { cost } else { f64::INFINITY }
+}
+
+fn all_finite(x: &[f64]) -> bool {
+    x.iter().all(|x| x.is_finite())
+}
+
+// Solve the square system `a x = b` by Gaussian elimination with partial pivoting. `None` if the
+// system is singular or not finite
+fn solve_linear(mut a: Vec<Vec<f64>>, mut b: Vec<f64>) -> Option<Vec<f64>> {
+    if !all_finite(&b) || !a.iter().all(|row| all_finite(row)) {
+        return None;
+    }
+    let n = b.len();
+    for col in 0 .. n {
+        let pivot = (col .. n).fold(col, |best, i| if a[i][col].abs() > a[best][col].abs() { i } else { best });
+        if a[pivot][col] == 0.0 {
+            return None;
+        }
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+        for row in col + 1 .. n {
+            let factor = a[row][col] / a[col][col];
+            for k in col .. n {
+                a[row][k] -= factor * a[col][k];
+            }
+            b[row] -= factor * b[col];
+        }
+    }
+    let mut x = vec![0.; n];
+    for row in (0 .. n).rev() {
+        let sum = (row + 1 .. n).map(|k| a[row][k] * x[k]).sum::<f64>();
+        x[row] = (b[row] - sum) / a[row][row];
+    }
+    if all_finite(&x) { Some(x) } else { None }
+}
+
+/// Fit the box and passive radiator to `alignment` by Levenberg-Marquardt, starting from the
+/// current ones. `params` is left unchanged
+pub fn align(params: &Parameters, alignment: Alignment) -> Result<AlignmentFit, AlignmentError> {
+    let (Cab, Mmp) = (params.Cab.v(), params.Mmp.v());
+    let fit = fit(params, alignment);
+    params.set(&params.Cab, Cab);
+    params.set(&params.Mmp, Mmp);
+    fit
+}
+
+// The fit for `align`, leaving the fitted box and passive radiator set in `params`
+fn fit(params: &Parameters, alignment: Alignment) -> Result<AlignmentFit, AlignmentError> {
+    let (Cab, Mmp) = (params.Cab.v(), params.Mmp.v());
+    let limits = [(params.Cab.min().max(1e-12).ln(), params.Cab.max().ln()),
+                  (params.Mmp.min().max(1e-12).ln(), params.Mmp.max().ln())];
+
+    let mut u = vec![Cab.ln(), Mmp.ln()];
+    if alignment.is_family() {
+        u.push(0.1);
+    }
+    let mut r = residuals(params, alignment, &u);
+    if !all_finite(&r) {
+        return Err(AlignmentError::NotFinite(alignment));
+    }
+    let mut λ = 1e-3;
+    for _ in 0 .. 200 {
+        if cost(&r) < 1e-24 {
+            break;
+        }
+
+        // Forward difference Jacobian, then the damped normal equations
+        let h = 1e-6;
+        let jacobian = (0 .. u.len()).map(|j| {
+            let mut stepped = u.clone();
+            stepped[j] += h;
+            residuals(params, alignment, &stepped).iter().zip(r.iter())
+                .map(|(a, b)| (a - b) / h)
+                .collect::<Vec<f64>>()
+        }).collect::<Vec<Vec<f64>>>();
+        if !jacobian.iter().all(|column| all_finite(column)) {
+            return Err(AlignmentError::NotFinite(alignment));
+        }
+        let jtj = (0 .. u.len()).map(|i| (0 .. u.len())
+            .map(|j| jacobian[i].iter().zip(jacobian[j].iter()).map(|(a, b)| a * b).sum::<f64>())
+            .collect::<Vec<f64>>()
+        ).collect::<Vec<Vec<f64>>>();
+        let jtr = jacobian.iter()
+            .map(|column| -column.iter().zip(r.iter()).map(|(a, b)| a * b).sum::<f64>())
+            .collect::<Vec<f64>>();
+
+        let mut improved = false;
+        while λ < 1e12 {
+            let mut damped = jtj.clone();
+            for i in 0 .. u.len() {
+                damped[i][i] *= 1.0 + λ;
+            }
+            let step = match solve_linear(damped, jtr.clone()) {
+                Some(step) => step,
+                None => {
+                    λ *= 3.0;
+                    continue;
+                },
+            };
+            let mut trial = u.iter().zip(step.iter()).map(|(u, s)| u + s).collect::<Vec<f64>>();
+            for (x, &(min, max)) in trial.iter_mut().zip(limits.iter()) {
+                *x = x.max(min).min(max);
+            }
+            let trial_r = residuals(params, alignment, &trial);
+            if cost(&trial_r) < cost(&r) {
+                u = trial;
+                r = trial_r;
+                λ = (λ / 3.0).max(1e-12);
+                improved = true;
+                break;
+            }
+            λ *= 3.0;
+        }
+        if !improved {
+            break;
+        }
+    }
+
+    residuals(params, alignment, &u);
+    Ok(AlignmentFit {
+        alignment,
+        Cab: params.Cab.v(),
+        Vb: params.Vb.v(),
+        Mmp: params.Mmp.v(),
+        Fp: params.Fp.v(),
+        Fb: params.Fb.v(),
+        k: if alignment.is_family() { u[2].abs() } else { 0.0 },
+        error: (cost(&r) / r.len() as f64).sqrt(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use parameters::builtin_defaults;
+
+    // Make the system lossless and set the driver's Qs, Cmp, Cab and Mmp so that it has exactly the
+    // `target` denominator, with δ / α = `r`. Returns Cab and Mmp
+    //
+    // With time in units of Ts and x = Tp / Ts, the lossless denominator is
+    // x^2 s^4 + x^2 / Qs s^3 + (x^2 (α + 1) + δ + 1) s^2 + (δ + 1) / Qs s + α + δ + 1. Matching it
+    // to the target at natural frequency w, with q = t3 / t1 and p = t2 - q, gives α + 1 = p w^2,
+    // δ + 1 = q x^2 w^2 and x^2 w^4 = α + δ + 1, which leave a quadratic in w^2
+    fn reachable(P: &Parameters, target: [f64; 3], r: f64) -> (f64, f64) {
+        let [t1, t2, t3] = target;
+        let (q, p) = (t3 / t1, t2 - t3 / t1);
+        let (a, b, c) = (r * p, 1.0 - r - p * q * (r + 1.0), r * q);
+        let W = (-b + (b * b - 4.0 * a * c).sqrt()) / (2.0 * a);
+        let x2 = (r * p * W + 1.0 - r) / (q * W);
+        let α = p * W - 1.0;
+        assert!(α > 0.0 && x2 > 0.0);
+
+        for loss in [&P.QL, &P.QA, &P.QP].iter() {
+            P.set(loss, 1e15);
+        }
+        P.set(&P.Rmp, 0.0);
+        P.solve(&P.Qts, "Bl", 1.0 / (t1 * W.sqrt())).unwrap();
+        let (Cas, Sp2) = (P.Cas.v(), P.Sp.v().powi(2));
+        P.set(&P.Cmp, r * Cas / Sp2);
+        let Cab = Cas / α;
+        let ωp = P.ωs.v() / x2.sqrt();
+        let Mmp = Sp2 / (ωp * ωp * P.Cap.v());
+        P.set(&P.Cab, Cab);
+        P.set(&P.Mmp, Mmp);
+        (Cab, Mmp)
+    }
+
+    #[test]
+    fn reaches_alignments() {
+        let cases = [(Alignment::B4, 0.0), (Alignment::QB3, 0.8), (Alignment::C4, 0.3)];
+        for &(alignment, k) in cases.iter() {
+            let P = builtin_defaults().unwrap();
+            let target = alignment.target(k);
+            let (Cab, Mmp) = reachable(&P, target, 0.5);
+            let system = normalised(&Radiator(&P).den);
+            assert!(system.iter().zip(target.iter()).all(|(s, t)| (s / t - 1.0).abs() < 1e-9), "{}", alignment.name());
+
+            // Found again from a different box
+            P.set(&P.Cab, Cab * 1.3);
+            P.set(&P.Mmp, Mmp * 0.8);
+            let fit = align(&P, alignment).unwrap();
+            assert!(fit.error < 1e-6, "{} {}", alignment.name(), fit.error);
+            assert!((fit.Cab / Cab - 1.0).abs() < 1e-4 && (fit.Mmp / Mmp - 1.0).abs() < 1e-4, "{}", alignment.name());
+            assert!((fit.k - k).abs() < 1e-4, "{} {}", alignment.name(), fit.k);
+        }
+    }
+
+    #[test]
+    fn rejects_undefined_response() {
+        // No mechanical losses make Qms infinite, and Qs = Qe Qms / (Qe + Qms) is then NaN
+        let P = builtin_defaults().unwrap();
+        let (Cab, Mmp) = (P.Cab.v(), P.Mmp.v());
+        P.set(&P.Rms, 0.0);
+        for alignment in Alignment::ALL.iter().cloned() {
+            assert_eq!(align(&P, alignment).err(), Some(AlignmentError::NotFinite(alignment)));
+            assert_eq!((P.Cab.v(), P.Mmp.v()), (Cab, Mmp));
+        }
+        assert_eq!(solve_linear(vec![vec![0.0]], vec![1.0]), None);
+        assert_eq!(solve_linear(vec![vec![::std::f64::NAN]], vec![1.0]), None);
+    }
+
+    #[test]
+    fn alignments() {
+        // QB3 and C4 tend to Butterworth as their member goes to zero
+        let b4 = Alignment::B4.target(0.0);
+        assert!((b4[0] - 2.6131259).abs() < 1e-6 && (b4[1] - 3.4142136).abs() < 1e-6);
+        for alignment in [Alignment::QB3, Alignment::C4].iter() {
+            let start = alignment.target(1e-20);
+            assert!(start.iter().zip(b4.iter()).all(|(a, b)| (a - b).abs() < 1e-6));
+        }
+
+        let P = builtin_defaults().unwrap();
+        let (Cab, Mmp) = (P.Cab.v(), P.Mmp.v());
+        for alignment in Alignment::ALL.iter().cloned() {
+            let fit = align(&P, alignment).unwrap();
+            assert_eq!((P.Cab.v(), P.Mmp.v()), (Cab, Mmp));
+
+            // Applying the fit gives the system it reports
+            fit.apply(&P);
+            assert_eq!((P.Vb.v(), P.Fp.v(), P.Fb.v()), (fit.Vb, fit.Fp, fit.Fb));
+            let system = normalised(&Radiator(&P).den);
+            let target = alignment.target(fit.k);
+            for (s, t) in system.iter().zip(target.iter()) {
+                assert!((s / t).ln().abs() <= 3.0 * fit.error + 1e-9, "{}", alignment.name());
+            }
+            P.set(&P.Cab, Cab);
+            P.set(&P.Mmp, Mmp);
+        }
+    }
+}
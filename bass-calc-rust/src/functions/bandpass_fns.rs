@@ -0,0 +1,201 @@
+//! Bandpass enclosures, built from the lumped acoustic circuit of the driver and its two chambers
+//!
+//! The driver's acoustic impedance `Zd = (s^2 Ts^2 + s Ts / Q + 1) / (s Cas)` is in series with the
+//! rear and front chamber impedances. A sealed chamber is `1 / (s C)`, and a vented chamber is its
+//! port mass in parallel with its compliance, `s M / (s^2 M C + 1)`. Each impedance is kept as a
+//! numerator and denominator polynomial, highest power first, so any order of system can be built.
+//! Responses are relative to the driver's own passband level on an infinite baffle
+
+use parameters::Parameters;
+use functions::{BassFnData, poly_add, poly_mul};
+
+/// Acoustic impedance as numerator and denominator polynomials in s
+struct AcousticImpedance {
+    num: Vec<f64>,
+    den: Vec<f64>,
+}
+
+fn driver(params: &Parameters, Q: f64) -> AcousticImpedance {
+    let Ts = params.Ts.v();
+    AcousticImpedance {
+        num: vec![Ts.powf(2.), Ts / Q, 1.],
+        den: vec![params.Cas.v(), 0.],
+    }
+}
+
+fn sealed_chamber(C: f64) -> AcousticImpedance {
+    AcousticImpedance { num: vec![1.], den: vec![C, 0.] }
+}
+
+fn vented_chamber(C: f64, M: f64) -> AcousticImpedance {
+    AcousticImpedance { num: vec![M, 0.], den: vec![M * C, 0., 1.] }
+}
+
+// Numerator of Zd + Zr + Zf over the common denominator Dd * Dr * Df
+fn series_num(d: &AcousticImpedance, r: &AcousticImpedance, f: &AcousticImpedance) -> Vec<f64> {
+    let dr = poly_mul(&poly_mul(&d.num, &r.den), &f.den);
+    let rf = poly_mul(&poly_mul(&d.den, &r.num), &f.den);
+    let fd = poly_mul(&poly_mul(&d.den, &r.den), &f.num);
+    poly_add(&poly_add(&dr, &rf), &fd)
+}
+
+// Chambers of each model
+fn chambers(params: &Parameters, rear_vented: bool) -> (AcousticImpedance, AcousticImpedance) {
+    let rear = if rear_vented {
+        vented_chamber(params.Car.v(), params.Mar.v())
+    } else {
+        sealed_chamber(params.Car.v())
+    };
+    (rear, vented_chamber(params.Caf.v(), params.Maf.v()))
+}
+
+// Cone volume velocity is U = Dd * Dr * Df / series_num, for unit drive pressure. Far field
+// pressure is s times the volume velocity leaving the ports, relative to Mas, the level of the
+// driver's passband
+fn radiator(params: &Parameters, rear_vented: bool) -> BassFnData {
+    let d = driver(params, params.Qs.v());
+    let (r, f) = chambers(params, rear_vented);
+
+    // The front port passes U / Df. A vented rear chamber's port passes -U / Dr
+    let ports = if rear_vented {
+        poly_add(&r.den, &f.den.iter().map(|x| -x).collect::<Vec<f64>>())
+    } else {
+        r.den.clone()
+    };
+    let smas = vec![params.Mas.v(), 0.];
+
+    BassFnData {
+        num: poly_mul(&poly_mul(&smas, &d.den), &ports),
+        den: series_num(&d, &r, &f),
+    }
+}
+
+// Cone excursion is U / (s Sd), relative to its static value for the driver alone, Cas / Sd
+fn displacement(params: &Parameters, rear_vented: bool) -> BassFnData {
+    let d = driver(params, params.Qs.v());
+    let (r, f) = chambers(params, rear_vented);
+
+    BassFnData {
+        num: poly_mul(&r.den, &f.den),
+        den: series_num(&d, &r, &f),
+    }
+}
+
+// The front port passes U / Df, so its air moves U / (s Df Nvf Svf). Relative to the driver's
+// static excursion Cas / Sd that is Dr / series_num scaled by the ratio of cone to port area
+fn front_port_displacement(params: &Parameters, rear_vented: bool) -> BassFnData {
+    let d = driver(params, params.Qs.v());
+    let (r, f) = chambers(params, rear_vented);
+    let area_ratio = params.Sd.v() / (params.Nvf.v() * params.Svf.v());
+
+    BassFnData {
+        num: r.den.iter().map(|x| x * area_ratio).collect(),
+        den: series_num(&d, &r, &f),
+    }
+}
+
+// The electrical damping adds s Ts / Qe to the driver's numerator, so Z / (Re + Rg) is the ratio
+// of the series numerators with total and mechanical Q
+fn impedance(params: &Parameters, rear_vented: bool) -> BassFnData {
+    let (r, f) = chambers(params, rear_vented);
+
+    BassFnData {
+        num: series_num(&driver(params, params.Qs.v()), &r, &f),
+        den: series_num(&driver(params, params.Qms.v()), &r, &f),
+    }
+}
+
+/// Sound pressure from the front port, for a sealed rear chamber and a vented front chamber tuned
+/// to `Ff`
+pub fn Bandpass4Radiator(params: &Parameters) -> BassFnData {
+    radiator(params, false)
+}
+
+/// Cone excursion of the 4th order bandpass, which is held down around `Ff` by the front chamber
+pub fn Bandpass4Displacement(params: &Parameters) -> BassFnData {
+    displacement(params, false)
+}
+
+/// Excursion of the air in the front ports of the 4th order bandpass, relative to the driver's
+/// static excursion. It peaks at `Ff`, and the sealed rear chamber holds it to zero at DC
+pub fn Bandpass4PortDisplacement(params: &Parameters) -> BassFnData {
+    front_port_displacement(params, false)
+}
+
+/// Electrical impedance of the 4th order bandpass over `Re + Rg`, with peaks either side of `Ff`
+pub fn Bandpass4Impedance(params: &Parameters) -> BassFnData {
+    impedance(params, false)
+}
+
+/// Sound pressure from both ports, with the rear chamber tuned to `Fr` and the front to `Ff`. The
+/// rear port is out of phase with the front one
+pub fn Bandpass6Radiator(params: &Parameters) -> BassFnData {
+    radiator(params, true)
+}
+
+/// Cone excursion of the 6th order bandpass, with a minimum at each chamber's tuning
+pub fn Bandpass6Displacement(params: &Parameters) -> BassFnData {
+    displacement(params, true)
+}
+
+/// Excursion of the air in the front ports of the 6th order bandpass, relative to the driver's
+/// static excursion. These carry the upper half of the passband, around `Ff`
+pub fn Bandpass6PortDisplacement(params: &Parameters) -> BassFnData {
+    front_port_displacement(params, true)
+}
+
+/// Electrical impedance of the 6th order bandpass over `Re + Rg`. Each chamber tuning adds a dip
+/// between two peaks
+pub fn Bandpass6Impedance(params: &Parameters) -> BassFnData {
+    impedance(params, true)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use functions::bass_fn_point;
+    use parameters::builtin_defaults;
+    use std::f64::consts::PI;
+
+    // Slope in powers of frequency between w and 2w
+    fn slope(data: &BassFnData, w: f64) -> f64 {
+        (bass_fn_point(data, 2.0 * w) / bass_fn_point(data, w)).log2()
+    }
+
+    #[test]
+    fn bandpass_slopes() {
+        let P = builtin_defaults().unwrap();
+        let ωf = 2.0 * PI * P.Ff.v();
+
+        // 12 dB/octave either side of the passband for 4th order
+        let bp4 = Bandpass4Radiator(&P);
+        assert!((slope(&bp4, 1e-3 * ωf) - 2.0).abs() < 1e-3);
+        assert!((slope(&bp4, 1e3 * ωf) + 2.0).abs() < 1e-3);
+
+        // For 6th order the two ports cancel at low frequencies, giving 24 dB/octave below and
+        // 12 dB/octave above
+        let bp6 = Bandpass6Radiator(&P);
+        assert!((slope(&bp6, 1e-3 * ωf) - 4.0).abs() < 1e-3);
+        assert!((slope(&bp6, 1e3 * ωf) + 2.0).abs() < 1e-3);
+
+        // The cone barely moves at the front chamber tuning, and the impedance is Re at DC
+        let excursion = Bandpass4Displacement(&P);
+        assert!(bass_fn_point(&excursion, ωf) < bass_fn_point(&excursion, 0.5 * ωf));
+        assert!((bass_fn_point(&Bandpass6Impedance(&P), 1e-6 * ωf) - 1.0).abs() < 1e-6);
+
+        // The 4th order's sound all comes from the front port, so the port air's acceleration
+        // gives the response. Normalised to Mas, that is (w Ts)^2 times the port excursion over
+        // the area ratio
+        let port = Bandpass4PortDisplacement(&P);
+        let area_ratio = P.Sd.v() / (P.Nvf.v() * P.Svf.v());
+        for &w in [0.5 * ωf, ωf, 2.0 * ωf].iter() {
+            let expected = (w * P.Ts.v()).powi(2) / area_ratio * bass_fn_point(&port, w);
+            assert!((bass_fn_point(&bp4, w) / expected - 1.0).abs() < 1e-9);
+        }
+
+        // At DC the 6th order's front port takes all the volume the cone displaces
+        let port = Bandpass6PortDisplacement(&P);
+        let cone = bass_fn_point(&Bandpass6Displacement(&P), 1e-6 * ωf);
+        assert!((bass_fn_point(&port, 1e-6 * ωf) / (cone * area_ratio) - 1.0).abs() < 1e-6);
+    }
+}
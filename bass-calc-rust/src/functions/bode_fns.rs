@@ -0,0 +1,116 @@
+//! Full frequency response of a transfer function: complex value, phase and group delay, like the
+//! prototype's `signal.bode`
+//!
+//! Frequencies are in Hz, and each transfer function is evaluated at s = j 2π f
+
+use std::f64::consts::PI;
+use functions::graph_fns::{BassFnData, C64, bass_fn_complex, poly_calc};
+
+pub struct Bode {
+    /// Frequencies of the sweep, in Hz
+    pub freqs: Vec<f64>,
+    pub response: Vec<C64>,
+    /// Phase in degrees, unwrapped so that it is continuous across the sweep
+    pub phase: Vec<f64>,
+    /// Group delay in ms
+    pub group_delay: Vec<f64>,
+}
+
+impl Bode {
+    /// Magnitude relative to the passband
+    pub fn magnitude(&self) -> Vec<f64> {
+        self.response.iter().map(|h| h.norm()).collect()
+    }
+
+    /// Magnitude in dB relative to the passband
+    pub fn magnitude_db(&self) -> Vec<f64> {
+        self.response.iter().map(|h| 20.0 * h.norm().log10()).collect()
+    }
+}
+
+/// `points` frequencies from `min_freq` to `max_freq` inclusive, evenly spaced on a log scale
+pub fn log_sweep(min_freq: f64, max_freq: f64, points: usize) -> Vec<f64> {
+    if points < 2 {
+        return vec![min_freq; points];
+    }
+    let ratio = (max_freq / min_freq).ln() / (points - 1) as f64;
+    (0 .. points).map(|i| min_freq * (ratio * i as f64).exp()).collect()
+}
+
+// Coefficients of dP/ds, highest power first
+fn poly_deriv(p: &[f64]) -> Vec<f64> {
+    let order = p.len().saturating_sub(1);
+    p.iter().take(order).enumerate().map(|(i, a)| a * (order - i) as f64).collect()
+}
+
+// d(arg P(jw)) / dw = Re(P'(jw) / P(jw)). Exact zeros of P give NaN
+fn phase_slope(p: &[f64], w: f64) -> f64 {
+    (poly_calc(&poly_deriv(p), w) / poly_calc(p, w)).re
+}
+
+/// Evaluate `data` over `freqs`, which should be in increasing order for the phase to unwrap
+/// correctly
+pub fn bode(data: &BassFnData, freqs: &[f64]) -> Bode {
+    let ws = freqs.iter().map(|f| 2.0 * PI * f).collect::<Vec<f64>>();
+    let response = ws.iter().map(|&w| bass_fn_complex(data, w)).collect::<Vec<C64>>();
+
+    // Take the jump of more than half a turn between neighbouring points back out
+    let mut phase = Vec::with_capacity(response.len());
+    let mut previous: Option<f64> = None;
+    for h in response.iter() {
+        let mut degrees = h.arg().to_degrees();
+        if let Some(previous) = previous {
+            degrees -= 360.0 * ((degrees - previous) / 360.0).round();
+        }
+        phase.push(degrees);
+        previous = Some(degrees);
+    }
+
+    // Group delay is -d(arg H) / dw, in ms
+    let group_delay = ws.iter()
+        .map(|&w| 1000.0 * (phase_slope(&data.den, w) - phase_slope(&data.num, w)))
+        .collect();
+
+    Bode { freqs: freqs.to_vec(), response, phase, group_delay }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use functions::SealedRadiator;
+    use parameters::builtin_defaults;
+
+    #[test]
+    fn sealed_phase_and_delay() {
+        let P = builtin_defaults().unwrap();
+        let Fc = P.Fc.v();
+        let Tc = 1.0 / (2.0 * PI * Fc);
+        let Qtc = P.Qtc.v();
+        let data = SealedRadiator(&P);
+
+        // A second order high pass leads by 180° well below Fc and by 90° at Fc
+        let freqs = log_sweep(Fc / 1000.0, Fc * 1000.0, 601);
+        let response = bode(&data, &freqs);
+        assert!((response.phase[0] - 180.0).abs() < 0.1);
+        assert!((response.phase[300] - 90.0).abs() < 1e-6);
+        assert!(response.phase[600].abs() < 0.1);
+
+        // Group delay starts at Tc / Qtc, and matches the slope of the phase
+        assert!((response.group_delay[0] - 1000.0 * Tc / Qtc).abs() < 1e-3);
+        let slope = -(response.phase[301] - response.phase[299]).to_radians()
+            / (2.0 * PI * (freqs[301] - freqs[299]));
+        assert!((response.group_delay[300] / (1000.0 * slope) - 1.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn phase_unwraps() {
+        // A delay line approximated by a 4th order all pass turns through more than a full circle
+        let data = BassFnData {
+            num: vec![1.0, -20.0, 180.0, -840.0, 1680.0],
+            den: vec![1.0, 20.0, 180.0, 840.0, 1680.0],
+        };
+        let response = bode(&data, &log_sweep(0.01, 1000.0, 400));
+        assert!(response.phase.windows(2).all(|pair| pair[1] < pair[0] && pair[0] - pair[1] < 180.0));
+        assert!((response.phase[399] + 720.0).abs() < 1.0);
+    }
+}
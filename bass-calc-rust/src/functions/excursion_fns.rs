@@ -0,0 +1,131 @@
+//! Absolute excursion of the driver and passive radiator, and the output it limits
+//!
+//! Excursions are peak values in mm, for a sine wave of `power` W RMS from the source into `Re`
+//! and `Rg`, so they can be compared directly with `Xmax`. They include the effect of the voice
+//! coil inductance on the current
+
+use std::f64::consts::SQRT_2;
+use parameters::Parameters;
+use functions::{C64, Model, SemiInductance, bode, electrical_response, passband_spl, response_db};
+
+/// Peak static excursion of the driver in free air for `power` W, in mm
+pub fn static_excursion(params: &Parameters, power: f64) -> f64 {
+    let current = SQRT_2 * (power / (params.Re.v() + params.Rg.v())).sqrt();
+    1000.0 * params.Bl.v() * current * params.Cms.v()
+}
+
+/// Peak driver excursion in mm over `freqs` in Hz, for `power` W
+pub fn excursion(params: &Parameters, model: Model, inductance: SemiInductance, power: f64,
+        freqs: &[f64]) -> Vec<f64> {
+    let x0 = static_excursion(params, power) * model.displacement_scale(params);
+    let displacement = (model.displacement())(params);
+    let electrical = electrical_response(params, model, inductance, freqs);
+    scaled(x0, &bode(&displacement, freqs).response, &electrical)
+}
+
+/// Peak excursion in mm of the passive radiator or the air in the ports, for models with either
+pub fn radiator_excursion(params: &Parameters, model: Model, inductance: SemiInductance, power: f64,
+        freqs: &[f64]) -> Option<Vec<f64>> {
+    let displacement = (model.radiator_displacement()?)(params);
+    let x0 = static_excursion(params, power) * model.radiator_displacement_scale(params);
+    let electrical = electrical_response(params, model, inductance, freqs);
+    Some(scaled(x0, &bode(&displacement, freqs).response, &electrical))
+}
+
+// Magnitude of x0 times each normalised displacement and electrical response
+fn scaled(x0: f64, displacement: &[C64], electrical: &[C64]) -> Vec<f64> {
+    displacement.iter().zip(electrical.iter()).map(|(x, e)| x0 * (x * e).norm()).collect()
+}
+
+// Power at which `excursion` in mm at 1 W reaches `xmax` in mm. Excursion grows with the square
+// root of power
+fn power_for(xmax: f64, excursion: &[f64]) -> Vec<f64> {
+    excursion.iter().map(|x| (xmax / x).powi(2)).collect()
+}
+
+/// Input power in W at which the driver reaches `Xmax`, over `freqs`
+pub fn xmax_power(params: &Parameters, model: Model, inductance: SemiInductance, freqs: &[f64]) -> Vec<f64> {
+    power_for(1000.0 * params.Xmax.v(), &excursion(params, model, inductance, 1.0, freqs))
+}
+
+/// Input power in W at which the passive radiator reaches `Xmaxp`. Port air has no such limit
+pub fn xmaxp_power(params: &Parameters, model: Model, inductance: SemiInductance,
+        freqs: &[f64]) -> Option<Vec<f64>> {
+    if model != Model::PassiveRadiator {
+        return None;
+    }
+    radiator_excursion(params, model, inductance, 1.0, freqs)
+        .map(|excursion| power_for(1000.0 * params.Xmaxp.v(), &excursion))
+}
+
+/// Highest input power in W over `freqs` that keeps the driver and passive radiator within their
+/// excursion limits and the driver within its thermal rating `Pe`. `Rg` takes its share of the
+/// input power
+pub fn max_power(params: &Parameters, model: Model, inductance: SemiInductance, freqs: &[f64]) -> Vec<f64> {
+    let mut power = xmax_power(params, model, inductance, freqs);
+    if let Some(radiator) = xmaxp_power(params, model, inductance, freqs) {
+        for (p, r) in power.iter_mut().zip(radiator.iter()) {
+            *p = p.min(*r);
+        }
+    }
+    let Re = params.Re.v();
+    let Pe = params.Pe.v() * (Re + params.Rg.v()) / Re;
+    power.iter().map(|p| p.min(Pe)).collect()
+}
+
+/// Maximum SPL in dB at distance `r` over `freqs`, at the input power `max_power` allows
+pub fn max_spl(params: &Parameters, model: Model, inductance: SemiInductance, freqs: &[f64]) -> Vec<f64> {
+    let response = response_db(params, model, inductance, freqs);
+    let r = params.r.v();
+    max_power(params, model, inductance, freqs).iter().zip(response.iter())
+        .map(|(&power, db)| passband_spl(params, power, r) + db)
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use parameters::builtin_defaults;
+
+    fn nearly_equal(a: f64, b: f64) -> bool {
+        ((a - b) / b).abs() < 1e-6
+    }
+
+    #[test]
+    fn excursion_limits() {
+        let P = builtin_defaults().unwrap();
+        let x0 = static_excursion(&P, 1.0);
+        let inductance = SemiInductance::LR2;
+        let (α, δ) = (P.α.v(), P.δ.v());
+        let low = P.Fs.v() / 1e4;
+
+        // At very low frequencies a sealed box stiffens the suspension, while the passive radiator
+        // moves just enough to take the volume the box doesn't
+        let sealed = excursion(&P, Model::Sealed, inductance, 1.0, &[low]);
+        assert!(nearly_equal(sealed[0], x0 / (1.0 + α)));
+        let driver = excursion(&P, Model::PassiveRadiator, inductance, 1.0, &[low]);
+        let radiator = radiator_excursion(&P, Model::PassiveRadiator, inductance, 1.0, &[low]).unwrap();
+        assert!(nearly_equal(P.Sp.v() * radiator[0], P.Sd.v() * driver[0] * δ / (1.0 + δ)));
+        assert!(radiator_excursion(&P, Model::Sealed, inductance, 1.0, &[low]).is_none());
+
+        // Excursion grows with the square root of power, up to Xmax at `xmax_power`
+        let freqs = [20.0, 50.0, 100.0];
+        for model in Model::ALL.iter().cloned() {
+            let power = xmax_power(&P, model, inductance, &freqs);
+            for (i, &f) in freqs.iter().enumerate() {
+                let x = excursion(&P, model, inductance, power[i], &[f]);
+                assert!(nearly_equal(x[0], 1000.0 * P.Xmax.v()), "{}", model.name());
+            }
+
+            // No more than Pe, and the thermal limit sets max SPL wherever excursion doesn't
+            let limit = max_power(&P, model, inductance, &freqs);
+            let spl = max_spl(&P, model, inductance, &freqs);
+            let response = response_db(&P, model, inductance, &freqs);
+            for i in 0 .. freqs.len() {
+                assert!(limit[i] <= P.Pe.v() && limit[i] <= power[i]);
+                let thermal = passband_spl(&P, P.Pe.v(), P.r.v()) + response[i];
+                assert!(spl[i] <= thermal + 1e-9);
+            }
+        }
+    }
+}
@@ -4,31 +4,59 @@
 use std::f64::consts::PI;
 use parameters::Parameters;
 use num_complex::Complex64;
-type C64 = Complex64;
+pub type C64 = Complex64;
 
 pub struct BassFnData {
     pub num: Vec<f64>,
     pub den: Vec<f64>
 }
 
-fn poly_calc(vec: &Vec<f64>, w: f64) -> C64 {
-    vec.iter().rev().enumerate().fold(C64::new(0., 0.), |sum, (index, scale)| {
-        let i = C64::new(0.0, w.clone());
-        sum + scale * i.powf(index as f64)
-    })
+/// Evaluate a polynomial in s = jw with coefficients highest power first, by Horner's method
+pub fn poly_calc(vec: &[f64], w: f64) -> C64 {
+    let s = C64::new(0.0, w);
+    vec.iter().fold(C64::new(0., 0.), |sum, scale| sum * s + scale)
+}
+
+/// Sum of two polynomials with coefficients highest power first
+pub fn poly_add(a: &[f64], b: &[f64]) -> Vec<f64> {
+    let len = a.len().max(b.len());
+    let mut sum = vec![0.; len];
+    for (i, scale) in a.iter().enumerate() {
+        sum[len - a.len() + i] += scale;
+    }
+    for (i, scale) in b.iter().enumerate() {
+        sum[len - b.len() + i] += scale;
+    }
+    sum
+}
+
+/// Product of two polynomials with coefficients highest power first
+pub fn poly_mul(a: &[f64], b: &[f64]) -> Vec<f64> {
+    if a.is_empty() || b.is_empty() {
+        return vec![];
+    }
+    let mut product = vec![0.; a.len() + b.len() - 1];
+    for (i, x) in a.iter().enumerate() {
+        for (j, y) in b.iter().enumerate() {
+            product[i + j] += x * y;
+        }
+    }
+    product
+}
+
+/// Complex value of the transfer function `data` at s = jw
+pub fn bass_fn_complex(data: &BassFnData, w: f64) -> C64 {
+    poly_calc(&data.num, w) / poly_calc(&data.den, w)
 }
 
 // Calculate a single point on the graph represented by `data` at frequency `w`
 pub fn bass_fn_point(data: &BassFnData, w: f64) -> f64 {
-    let num = poly_calc(&data.num, w);
-    let den = poly_calc(&data.den, w);
-    let n = num / den;
-    n.norm_sqr().sqrt()
+    bass_fn_complex(data, w).norm()
 }
 
-#[allow(dead_code)]
-pub fn Radiator(params: &Parameters) -> BassFnData {
-    let g =  0.2; // τb / Ts 0.2 is a good guesstimate
+// Denominator of the radiator response, for a driver with total Q `Qs`
+fn radiator_den(params: &Parameters, Qs: f64) -> Vec<f64> {
+    let g = params.Γ.v(); // τb / Ts
     let α = params.α.v();
     let δ = params.δ.v();
     let psi = α + δ + 1.0;
@@ -37,11 +65,6 @@ pub fn Radiator(params: &Parameters) -> BassFnData {
     let Tp = params.Tp.v();
     let Tp2 = Tp.powf(2.);
     let Qmp = params.Qmp.v();
-    let Qs = params.Qs.v();
-
-    let b4 = Ts2 * Tp2;
-    let b3 = Ts2 * (Tp / Qmp);
-    let b2 = Ts2;
 
     let a4 = Ts2 * Tp2;
 
@@ -58,15 +81,30 @@ pub fn Radiator(params: &Parameters) -> BassFnData {
             Tp * (α + 1.) / Qmp +
             (g * Ts) * (α + δ);
 
+    vec![a4, a3, a2, a1, psi]
+}
+
+/// Sound pressure of the driver and passive radiator together, Hurlburt's complete response
+pub fn Radiator(params: &Parameters) -> BassFnData {
+    let Ts2 = params.Ts.v().powf(2.);
+    let Tp = params.Tp.v();
+    let Tp2 = Tp.powf(2.);
+    let Qmp = params.Qmp.v();
+
+    let b4 = Ts2 * Tp2;
+    let b3 = Ts2 * (Tp / Qmp);
+    let b2 = Ts2;
+
     BassFnData {
         num: vec![b4, b3, b2, 0., 0.],
-        den: vec![a4, a3, a2, a1, psi]
+        den: radiator_den(params, params.Qs.v())
     }
 }
 
-#[allow(dead_code)]
+/// Driver cone excursion relative to its value at DC, which dips where the passive radiator
+/// resonates with the box
 pub fn DriverDisplacement(params: &Parameters) -> BassFnData {
-    let g =  0.2; // τb / Ts 0.2 is a good guesstimate
+    let g = params.Γ.v(); // τb / Ts
     let Ts = params.Ts.v();
     let Ts2 = Ts.powf(2.);
     let Tp = params.Tp.v();
@@ -101,9 +139,9 @@ pub fn DriverDisplacement(params: &Parameters) -> BassFnData {
     }
 }
 
-#[allow(dead_code)]
+/// Passive radiator excursion relative to the driver's at DC
 pub fn PassiveDisplacement(params: &Parameters) -> BassFnData {
-    let g =  0.2; // τb / Ts 0.2 is a good guesstimate
+    let g = params.Γ.v(); // τb / Ts
     let α = params.α.v();
     let δ = params.δ.v();
     let psi = α + δ + 1.0;
@@ -134,70 +172,22 @@ pub fn PassiveDisplacement(params: &Parameters) -> BassFnData {
     }
 }
 
-#[allow(dead_code)]
+/// Electrical impedance of the passive radiator system over `Re + Rg`. The electrical damping only
+/// enters through `Qs`, so this is the ratio of the denominators with total and mechanical Q
 pub fn Impedance(params: &Parameters) -> BassFnData {
-    let g =  0.2; // τb / Ts 0.2 is a good guesstimate
-    let α = params.α.v();
-    let δ = params.δ.v();
-    let psi = α + δ + 1.0;
-    let Qs = params.Qs.v();
-    let Qms = params.Qms.v();
-    let Qes = params.Qes.v();
-    let Qmp = params.Qmp.v();
-    let Tb = params.Tb.v();
-    let Tb2 = Tb.powf(2.);
-    let Tp = params.Tp.v();
-    let Tp2 = Tp.powf(2.);
-    let Ts = params.Ts.v();
-    let Ts2 = Ts.powf(2.);
-    
-    let b3 = psi * Tb2 * Ts / Qes;
-    
-    let b1 = psi * Ts / Qes;
-
-    let a4 = Ts2 * Tp2;
-    let a3 = Ts2 * Tp / Qmp +
-            (g * Ts) * (α * Tp2 + (δ * Ts2));
-
-    let a2 = Tp2 * (α + 1.) +
-            Ts2 * (δ + 1.) +
-            (Ts * Tp) / (Qs * Qmp) +
-            (g * Ts) * ((α * Tp / Qmp) + (δ * Ts / Qms));
-
-    let a1 = Ts * (δ + 1.) / Qms +
-            Tp * (α + 1.) / Qmp +
-            (g * Ts) * (α + δ);
-
     BassFnData {
-        num: vec![0., b3, 0., b1, psi],
-        den: vec![a4, a3, a2, a1, psi]
+        num: radiator_den(params, params.Qs.v()),
+        den: radiator_den(params, params.Qms.v())
     }
 }
 
-/*
-pub fn EfficencySmalls(params: &Parameters) -> f64 {
-    let Qts = params.Qts.v();
-    let Qes = params.Qes.v();
-    let c = params.c.v();
-    let Vb = params.Vb.v();
-    let Vas = params.Vas.v();
-    let Fs = params.Fs.v();
-
-    let kηq = Qts / Qes;
-
-    let kηg = 4 * (PI.powi(2)) / c.powi(3) * (Vas / Vb) * (Fs.powi(3) / (f3 ** 3.)) * (1. / Qts);
-    let kη = kηq * kηg;
-
-    let η0 = kη * (f3 ** 3) * Vb;
-    η0
-}
-*/
-
+/// Reference efficiency by Adams' formulation, from the motor and moving mass. Like `η0`, it is
+/// relative to the power drawn from the source, including what `Rg` dissipates
 #[allow(dead_code)]
 pub fn EfficiencyAdams(params: &Parameters) -> f64 {
     let c = params.c.v();
     let ρ0 = params.ρ0.v();
-    let Re = params.Re.v();
+    let Re = params.Re.v() + params.Rg.v();
     let Sd = params.Sd.v();
     let Bl = params.Bl.v();
     let Mas = params.Mas.v();
@@ -205,3 +195,29 @@ pub fn EfficiencyAdams(params: &Parameters) -> f64 {
     let η0 = Bl.powi(2) * ρ0 / (Sd.powi(2) * Mas.powi(2) * 2. * PI * c * Re);
     η0
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use parameters::builtin_defaults;
+
+    #[test]
+    fn polynomials() {
+        // (s + 1)(s - 2) = s^2 - s - 2
+        assert_eq!(poly_mul(&[1., 1.], &[1., -2.]), vec![1., -1., -2.]);
+        assert_eq!(poly_add(&[1., -1., -2.], &[3., 2.]), vec![1., 2., 0.]);
+
+        // Any degree, including at w = 0
+        let data = BassFnData { num: vec![1., 0., 0., 0., 0., 0., 2.], den: vec![4.] };
+        assert_eq!(bass_fn_point(&data, 0.0), 0.5);
+        assert!((bass_fn_point(&data, 2.0) - (2.0f64 - 64.0).abs() / 4.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn adams_efficiency() {
+        // Adams' form and η0 agree, with Rg taking its share
+        let P = builtin_defaults().unwrap();
+        P.set(&P.Rg, P.Re.v());
+        assert!((EfficiencyAdams(&P) / P.η0.v() - 1.0).abs() < 1e-9);
+    }
+}
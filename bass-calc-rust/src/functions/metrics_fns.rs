@@ -0,0 +1,180 @@
+//! Figures of merit read off a response: where it rolls off, how high it peaks and how flat the
+//! passband is, and Small's efficiency for the box and bandwidth it gives
+//!
+//! Levels are in dB relative to the passband of the transfer function, which is 0 dB, and
+//! frequencies in Hz
+
+use std::f64::consts::PI;
+use parameters::Parameters;
+use functions::{BassFnData, Model, bode, log_sweep};
+
+/// Lowest and highest frequency in Hz, and number of points, of the sweep a model's metrics are
+/// read from
+pub const METRICS_SWEEP: (f64, f64, usize) = (2.0, 2000.0, 400);
+
+pub struct Metrics {
+    /// Frequencies where the response rolls off through -3, -6 and -10 dB, if it does within the
+    /// sweep
+    pub f3: Option<f64>,
+    pub f6: Option<f64>,
+    pub f10: Option<f64>,
+    /// Highest level in the sweep, and where it is
+    pub peak_db: f64,
+    pub peak_freq: f64,
+    /// Spread of the ripples in the passband above `f3`, including the 0 dB passband level
+    pub ripple: f64,
+}
+
+// Frequency where `db` falls through `level`, searching down from the peak at index `from`.
+// Interpolated on a log scale between points, so it moves smoothly with the parameters
+fn roll_off(freqs: &[f64], db: &[f64], from: usize, level: f64) -> Option<f64> {
+    (1 .. from + 1).rev()
+        .find(|&i| db[i - 1] < level && db[i] >= level)
+        .map(|i| {
+            let t = (level - db[i - 1]) / (db[i] - db[i - 1]);
+            freqs[i - 1] * (freqs[i] / freqs[i - 1]).powf(t)
+        })
+}
+
+/// Metrics of `data` over `freqs`, which should be in increasing order. For a band pass, the roll
+/// off points are those below the peak. None when `freqs` is empty, which has no peak
+pub fn metrics(data: &BassFnData, freqs: &[f64]) -> Option<Metrics> {
+    if freqs.is_empty() {
+        return None;
+    }
+    let db = bode(data, freqs).magnitude_db();
+    let peak = (0 .. db.len()).fold(0, |best, i| if db[i] > db[best] { i } else { best });
+    let f3 = roll_off(freqs, &db, peak, -3.0);
+
+    // Turning points above f3 give the ripples
+    let mut levels = vec![0.0];
+    for i in 1 .. db.len().saturating_sub(1) {
+        let turning = (db[i] - db[i - 1]) * (db[i + 1] - db[i]) <= 0.0;
+        if turning && freqs[i] > f3.unwrap_or(0.0) {
+            levels.push(db[i]);
+        }
+    }
+    let highest = levels.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    let lowest = levels.iter().cloned().fold(f64::INFINITY, f64::min);
+
+    Some(Metrics {
+        f3: f3,
+        f6: roll_off(freqs, &db, peak, -6.0),
+        f10: roll_off(freqs, &db, peak, -10.0),
+        peak_db: db[peak],
+        peak_freq: freqs[peak],
+        ripple: highest - lowest,
+    })
+}
+
+/// Metrics of the response of `model` over `METRICS_SWEEP`. This is the transfer function alone,
+/// without the voice coil inductance
+pub fn model_metrics(params: &Parameters, model: Model) -> Metrics {
+    let (min_freq, max_freq, points) = METRICS_SWEEP;
+    // The sweep always has points
+    metrics(&(model.response())(params), &log_sweep(min_freq, max_freq, points)).unwrap()
+}
+
+/// Small's alignment factor kη(G), which depends only on the shape of the response: the compliance
+/// ratio `α`, the total Q `Qt` at the system's resonance and the -3 dB frequency relative to that
+/// resonance, `f3_ratio`. Each alignment fixes these, as in Small's tables
+pub fn alignment_efficiency(α: f64, Qt: f64, f3_ratio: f64) -> f64 {
+    4. * PI.powi(2) * α / (Qt * f3_ratio.powi(3))
+}
+
+/// Small's efficiency constant kη of `model`, from the F3 of its response. It sets the trade
+/// between efficiency, bandwidth and box size, η0 = kη F3^3 Vb / c^3, and is the product of
+/// kη(Q) = Qs / Qe, from the driver's losses, and kη(G). A closed box resonates at `Fc` with `Qtc`
+/// and keeps α / (1 + α) of the driver's compliance, while vented and passive radiator boxes are
+/// aligned about the driver's own `Fs` and `Qs`. `None` for the bandpass boxes, whose output isn't
+/// set by one box volume, or if the response doesn't roll off within `METRICS_SWEEP`
+pub fn efficiency_constant(params: &Parameters, model: Model) -> Option<f64> {
+    let f3 = model_metrics(params, model).f3?;
+    let Qs = params.Qs.v();
+    let α = params.α.v();
+    let kηg = match model {
+        Model::Sealed => alignment_efficiency(α / (1. + α), params.Qtc.v(), f3 / params.Fc.v()),
+        Model::PassiveRadiator | Model::Vented => alignment_efficiency(α, Qs, f3 / params.Fs.v()),
+        Model::Bandpass4 | Model::Bandpass6 => return None,
+    };
+    Some(Qs / params.Qe.v() * kηg)
+}
+
+/// Reference efficiency by Small's formulation, for the alignment of `model` with the -3 dB
+/// frequency `f3` in Hz and the box `Vb`
+#[allow(dead_code)]
+pub fn EfficiencySmalls(params: &Parameters, model: Model, f3: f64) -> Option<f64> {
+    efficiency_constant(params, model).map(|kη| kη * f3.powi(3) * params.Vb.v() / params.c.v().powi(3))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::f64::consts::{PI, SQRT_2};
+    use functions::log_sweep;
+    use parameters::builtin_defaults;
+
+    #[test]
+    fn second_order_metrics() {
+        // A Butterworth high pass at 1 / 2π Hz is down N dB where w^4 = 1 / (10^(N / 10) - 1), and
+        // flat above it
+        let fc = 1.0 / (2.0 * PI);
+        let freqs = log_sweep(fc / 100.0, fc * 100.0, 2001);
+        let butterworth = metrics(&BassFnData { num: vec![1., 0., 0.], den: vec![1., SQRT_2, 1.] }, &freqs).unwrap();
+        let expected = |level: f64| (10f64.powf(level / 10.0) - 1.0).powf(-0.25) * fc;
+        assert!((butterworth.f3.unwrap() / expected(3.0) - 1.0).abs() < 1e-4);
+        assert!((butterworth.f6.unwrap() / expected(6.0) - 1.0).abs() < 1e-4);
+        assert!((butterworth.f10.unwrap() / expected(10.0) - 1.0).abs() < 1e-4);
+        assert!(butterworth.f3 > butterworth.f6 && butterworth.f6 > butterworth.f10);
+        assert!(butterworth.ripple < 1e-6);
+
+        // With Q 2 it peaks by Q^2 / sqrt(Q^2 - 1 / 4) above the corner
+        let q = 2.0;
+        let peaked = metrics(&BassFnData { num: vec![1., 0., 0.], den: vec![1., 1.0 / q, 1.] }, &freqs).unwrap();
+        let peak = 20.0 * (q * q / (q * q - 0.25).sqrt()).log10();
+        let peak_freq = fc / (1.0 - 1.0 / (2.0 * q * q)).sqrt();
+        assert!((peaked.peak_db - peak).abs() < 1e-3);
+        assert!((peaked.peak_freq / peak_freq - 1.0).abs() < 1e-2);
+        assert!((peaked.ripple - peak).abs() < 1e-3);
+
+        // Nothing rolls off within a sweep that stays in the passband
+        let flat = metrics(&BassFnData { num: vec![1., 0., 0.], den: vec![1., SQRT_2, 1.] }, &[10.0, 20.0]);
+        assert_eq!(flat.unwrap().f3, None);
+
+        // An empty sweep has nothing to read
+        assert!(metrics(&BassFnData { num: vec![1., 0., 0.], den: vec![1., SQRT_2, 1.] }, &[]).is_none());
+    }
+
+    #[test]
+    fn small_efficiency() {
+        // A closed box with α = 3 and Qtc 1 / √2 is down 3 dB at Fc, so Small's kη(G) is
+        // 4π^2 3/4 √2 = 41.87
+        let P = builtin_defaults().unwrap();
+        P.set(&P.Cab, P.Cas.v() / 3.0);
+        P.solve(&P.Qts, "Bl", 0.5f64.sqrt() / 2.0).unwrap();
+        let kηq = P.Qs.v() / P.Qe.v();
+        let kη = efficiency_constant(&P, Model::Sealed).unwrap();
+        assert!((kη / kηq / 41.87 - 1.0).abs() < 1e-2, "{}", kη / kηq);
+
+        // The efficiency it gives at that F3 and box is the driver's η0
+        let η0 = EfficiencySmalls(&P, Model::Sealed, P.Fc.v()).unwrap();
+        assert!((η0 / P.η0.v() - 1.0).abs() < 3e-2, "{}", η0 / P.η0.v());
+
+        // A lossless vented B4 has α = √2, Qt = 0.383 and F3 = Fs, for kη(G) 4π^2 √2 / 0.383 = 145.9
+        for loss in [&P.QL, &P.QA, &P.QP].iter() {
+            P.set(loss, 1e15);
+        }
+        P.solve(&P.Qts, "Bl", 1.0 / (4.0 + 8f64.sqrt()).sqrt()).unwrap();
+        P.set(&P.Cab, P.Cas.v() / 2f64.sqrt());
+        P.set(&P.Lv, 1.0);
+        P.solve(&P.Fv, "Dv", P.Fs.v()).unwrap();
+        let kηq = P.Qs.v() / P.Qe.v();
+        let kη = efficiency_constant(&P, Model::Vented).unwrap();
+        assert!((kη / kηq / 145.9 - 1.0).abs() < 1e-2, "{}", kη / kηq);
+        let η0 = EfficiencySmalls(&P, Model::Vented, P.Fs.v()).unwrap();
+        assert!((η0 / P.η0.v() - 1.0).abs() < 3e-2, "{}", η0 / P.η0.v());
+
+        // Neither bandpass box has one volume to trade against
+        assert!(efficiency_constant(&P, Model::Bandpass4).is_none());
+    }
+}
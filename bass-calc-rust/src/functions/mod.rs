@@ -1,5 +1,31 @@
 
+pub mod alignment_fns;
+pub mod bandpass_fns;
+pub mod bode_fns;
+pub mod excursion_fns;
 pub mod graph_fns;
+pub mod metrics_fns;
+pub mod models;
+pub mod optimize_fns;
+pub mod pole_zero_fns;
+pub mod sealed_fns;
+pub mod spl_fns;
+pub mod time_fns;
 pub mod validate_fns;
+pub mod vented_fns;
+pub mod voice_coil_fns;
 
+pub use self::alignment_fns::*;
+pub use self::bandpass_fns::*;
+pub use self::bode_fns::*;
+pub use self::excursion_fns::*;
 pub use self::graph_fns::*;
+pub use self::metrics_fns::*;
+pub use self::models::{Model, ModelFn};
+pub use self::optimize_fns::*;
+pub use self::pole_zero_fns::*;
+pub use self::sealed_fns::*;
+pub use self::spl_fns::*;
+pub use self::time_fns::*;
+pub use self::vented_fns::*;
+pub use self::voice_coil_fns::*;
@@ -0,0 +1,104 @@
+//! The enclosure types that can be modelled, and the graph functions for each
+
+use parameters::Parameters;
+use functions::bandpass_fns::*;
+use functions::graph_fns::*;
+use functions::sealed_fns::*;
+use functions::vented_fns::*;
+
+/// Function giving the transfer function of one aspect of a model
+pub type ModelFn = fn(&Parameters) -> BassFnData;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Model {
+    PassiveRadiator,
+    Sealed,
+    Vented,
+    Bandpass4,
+    Bandpass6,
+}
+
+impl Model {
+    pub const ALL: [Model; 5] = [
+        Model::PassiveRadiator, Model::Sealed, Model::Vented, Model::Bandpass4, Model::Bandpass6,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Model::PassiveRadiator => "Passive radiator",
+            Model::Sealed => "Sealed",
+            Model::Vented => "Vented",
+            Model::Bandpass4 => "4th order bandpass",
+            Model::Bandpass6 => "6th order bandpass",
+        }
+    }
+
+    /// Sound pressure relative to the passband
+    pub fn response(&self) -> ModelFn {
+        match *self {
+            Model::PassiveRadiator => Radiator,
+            Model::Sealed => SealedRadiator,
+            Model::Vented => VentedRadiator,
+            Model::Bandpass4 => Bandpass4Radiator,
+            Model::Bandpass6 => Bandpass6Radiator,
+        }
+    }
+
+    /// Driver cone excursion relative to its value at DC
+    pub fn displacement(&self) -> ModelFn {
+        match *self {
+            Model::PassiveRadiator => DriverDisplacement,
+            Model::Sealed => SealedDisplacement,
+            Model::Vented => VentedDisplacement,
+            Model::Bandpass4 => Bandpass4Displacement,
+            Model::Bandpass6 => Bandpass6Displacement,
+        }
+    }
+
+    /// Excursion of the passive radiator or port air relative to the driver's at DC. A sealed box
+    /// has neither, and for a bandpass it is the front port
+    pub fn radiator_displacement(&self) -> Option<ModelFn> {
+        match *self {
+            Model::PassiveRadiator => Some(PassiveDisplacement),
+            Model::Sealed => None,
+            Model::Vented => Some(PortDisplacement),
+            Model::Bandpass4 => Some(Bandpass4PortDisplacement),
+            Model::Bandpass6 => Some(Bandpass6PortDisplacement),
+        }
+    }
+
+    /// Static cone excursion that `displacement` is relative to, as a fraction of the driver's
+    /// static excursion in free air. The air in a closed box stiffens the suspension
+    pub fn displacement_scale(&self, params: &Parameters) -> f64 {
+        match *self {
+            Model::PassiveRadiator => {
+                let δ = params.δ.v();
+                (1.0 + δ) / (params.α.v() + δ + 1.0)
+            },
+            Model::Sealed => 1.0 / (1.0 + params.α.v()),
+            Model::Vented | Model::Bandpass4 | Model::Bandpass6 => 1.0,
+        }
+    }
+
+    /// Converts `radiator_displacement` to excursion relative to the driver's static excursion in
+    /// free air
+    pub fn radiator_displacement_scale(&self, params: &Parameters) -> f64 {
+        match *self {
+            Model::PassiveRadiator => {
+                let δ = params.δ.v();
+                params.Sd.v() / params.Sp.v() * δ / (params.α.v() + δ + 1.0)
+            },
+            _ => 1.0,
+        }
+    }
+
+    pub fn impedance(&self) -> ModelFn {
+        match *self {
+            Model::PassiveRadiator => Impedance,
+            Model::Sealed => SealedImpedance,
+            Model::Vented => VentedImpedance,
+            Model::Bandpass4 => Bandpass4Impedance,
+            Model::Bandpass6 => Bandpass6Impedance,
+        }
+    }
+}
@@ -0,0 +1,287 @@
+//! Search for the box design that best meets a goal, for one of the models
+//!
+//! The free parameters are moved within their slider ranges by Nelder-Mead, on a log scale where
+//! the range allows it. Responses and excursion limits come from the model's transfer functions,
+//! leaving out the voice coil inductance, which matters little at the frequencies that set the box
+
+use std::f64::consts::PI;
+use parameters::{Param, Parameters};
+use functions::{Model, PassiveDisplacement};
+use functions::{bass_fn_point, log_sweep, metrics, static_excursion};
+
+/// Ratio of the weight of a constraint to that of the goal, for a violation of 1 dB or of the
+/// whole excursion limit
+const PENALTY: f64 = 100.0;
+
+pub enum Goal {
+    /// Least mean square difference in dB from `target` at `freqs` in Hz
+    Target { freqs: Vec<f64>, target: Vec<f64> },
+    /// Lowest -3 dB frequency, with the passband ripple no more than `ripple` dB and the driver
+    /// and any passive radiator within `Xmax` and `Xmaxp` at the drive level `Pin`
+    Extension { ripple: f64 },
+}
+
+/// Best values found for the free parameters, in SI base units, with the cost after each
+/// iteration
+pub struct Optimized {
+    pub free: Vec<Param>,
+    pub best: Vec<f64>,
+    pub cost: f64,
+    pub history: Vec<f64>,
+}
+
+impl Optimized {
+    pub fn apply(&self, params: &Parameters) {
+        for (param, &value) in self.free.iter().zip(self.best.iter()) {
+            params.set(param, value);
+        }
+    }
+}
+
+/// Inputs that set the box for `model`, for `optimize` to move: the box and, when it has one, the
+/// port or passive radiator that tunes it. None for the bandpass boxes, whose chambers trade
+/// bandwidth against gain rather than extension
+pub fn free_params(params: &Parameters, model: Model) -> Option<Vec<Param>> {
+    match model {
+        Model::PassiveRadiator => Some(vec![params.Cab.clone(), params.Mmp.clone(), params.Sp.clone()]),
+        Model::Sealed => Some(vec![params.Cab.clone()]),
+        Model::Vented => Some(vec![params.Cab.clone(), params.Lv.clone()]),
+        Model::Bandpass4 | Model::Bandpass6 => None,
+    }
+}
+
+// Response in dB relative to the passband at `freq` in Hz
+fn response_db(params: &Parameters, model: Model, freq: f64) -> f64 {
+    20.0 * bass_fn_point(&(model.response())(params), 2.0 * PI * freq).log10()
+}
+
+// Peak excursion over `freqs` as a fraction of the limit, for the driver and any passive radiator.
+// Port air has no such limit
+fn excursion_ratio(params: &Parameters, model: Model, freqs: &[f64]) -> f64 {
+    let x0 = static_excursion(params, params.Pin.v()) / 1000.0;
+    let driver = (model.displacement())(params);
+    let driver_x0 = x0 * model.displacement_scale(params) / params.Xmax.v();
+    let radiator = match model {
+        Model::PassiveRadiator => {
+            Some((PassiveDisplacement(params), x0 * model.radiator_displacement_scale(params) / params.Xmaxp.v()))
+        },
+        _ => None,
+    };
+    freqs.iter().map(|f| 2.0 * PI * f)
+        .map(|w| {
+            let driver = driver_x0 * bass_fn_point(&driver, w);
+            radiator.as_ref().map_or(driver, |&(ref radiator, x0)| driver.max(x0 * bass_fn_point(radiator, w)))
+        })
+        .fold(0.0, f64::max)
+}
+
+/// Cost of the current parameters of `model` for `goal`. Lower is better
+pub fn design_cost(params: &Parameters, model: Model, goal: &Goal) -> f64 {
+    let cost = match *goal {
+        Goal::Target { ref freqs, ref target } => {
+            let squares = freqs.iter().zip(target.iter())
+                .map(|(&f, t)| (response_db(params, model, f) - t).powi(2))
+                .sum::<f64>();
+            squares / freqs.len() as f64
+        },
+        Goal::Extension { ripple } => {
+            let Fs = params.Fs.v();
+            let freqs = log_sweep(Fs / 10.0, Fs * 10.0, 200);
+            metrics(&(model.response())(params), &freqs).map_or(f64::INFINITY, |response| {
+                // Without a crossing the response either holds up to the bottom of the sweep or
+                // never reaches -3 dB at all, and a design with no passband is the worst there is
+                let f3 = match response.f3 {
+                    Some(f3) => f3,
+                    None if response.peak_db >= -3.0 => freqs[0],
+                    None => freqs[freqs.len() - 1],
+                };
+                let over_ripple = (response.ripple - ripple).max(0.0);
+                let over_xmax = (excursion_ratio(params, model, &freqs) - 1.0).max(0.0);
+                f3.ln() + PENALTY * (over_ripple.powi(2) + over_xmax.powi(2))
+            })
+        },
+    };
+    if cost.is_nan() { f64::INFINITY } else { cost }
+}
+
+// Maps between a parameter's value and its position 0 to 1 in its range, logarithmically when the
+// range is positive
+struct Scale {
+    min: f64,
+    max: f64,
+    log: bool,
+}
+
+impl Scale {
+    fn of(param: &Param) -> Scale {
+        let log = param.min() > 0.0;
+        if log {
+            Scale { min: param.min().ln(), max: param.max().ln(), log }
+        } else {
+            Scale { min: param.min(), max: param.max(), log }
+        }
+    }
+
+    fn position(&self, value: f64) -> f64 {
+        let value = if self.log { value.ln() } else { value };
+        ((value - self.min) / (self.max - self.min)).max(0.0).min(1.0)
+    }
+
+    fn value(&self, position: f64) -> f64 {
+        let value = self.min + position.max(0.0).min(1.0) * (self.max - self.min);
+        if self.log { value.exp() } else { value }
+    }
+}
+
+/// Search the `free` parameters for the lowest `design_cost` of `model`, starting from their current
+/// values, for at most `iterations`. Free parameters must be inputs rather than calculated, so `Cab`
+/// stands in for `Vb`. `params` is left unchanged
+pub fn optimize(params: &Parameters, model: Model, free: &[Param], goal: &Goal, iterations: usize) -> Optimized {
+    debug_assert!(free.iter().all(|param| param.update_fn.is_none()), "free parameters must be inputs");
+    let start = free.iter().map(|param| param.v()).collect::<Vec<f64>>();
+    let scales = free.iter().map(Scale::of).collect::<Vec<Scale>>();
+    let cost_at = |x: &[f64]| {
+        for ((param, scale), &position) in free.iter().zip(scales.iter()).zip(x.iter()) {
+            params.set(param, scale.value(position));
+        }
+        design_cost(params, model, goal)
+    };
+
+    // Simplex of the start and a step along each parameter, away from the nearer end of its range
+    let origin = start.iter().zip(scales.iter()).map(|(&v, scale)| scale.position(v)).collect::<Vec<f64>>();
+    let mut simplex = vec![origin.clone()];
+    for i in 0 .. free.len() {
+        let mut vertex = origin.clone();
+        vertex[i] += if vertex[i] > 0.5 { -0.1 } else { 0.1 };
+        simplex.push(vertex);
+    }
+    let mut costs = simplex.iter().map(|x| cost_at(x)).collect::<Vec<f64>>();
+
+    let mut history = vec![];
+    for _ in 0 .. iterations {
+        let mut order = (0 .. simplex.len()).collect::<Vec<usize>>();
+        order.sort_by(|&a, &b| costs[a].partial_cmp(&costs[b]).unwrap());
+        simplex = order.iter().map(|&i| simplex[i].clone()).collect();
+        costs = order.iter().map(|&i| costs[i]).collect();
+        history.push(costs[0]);
+        let worst = simplex.len() - 1;
+        if (costs[worst] - costs[0]).abs() < 1e-10 * (1.0 + costs[0].abs()) {
+            break;
+        }
+
+        let centroid = (0 .. free.len())
+            .map(|j| simplex[.. worst].iter().map(|x| x[j]).sum::<f64>() / worst as f64)
+            .collect::<Vec<f64>>();
+        let towards = |t: f64| centroid.iter().zip(simplex[worst].iter())
+            .map(|(c, w)| (c + t * (c - w)).max(0.0).min(1.0))
+            .collect::<Vec<f64>>();
+
+        let reflected = towards(1.0);
+        let reflected_cost = cost_at(&reflected);
+        if reflected_cost < costs[0] {
+            let expanded = towards(2.0);
+            let expanded_cost = cost_at(&expanded);
+            if expanded_cost < reflected_cost {
+                simplex[worst] = expanded;
+                costs[worst] = expanded_cost;
+            } else {
+                simplex[worst] = reflected;
+                costs[worst] = reflected_cost;
+            }
+        } else if reflected_cost < costs[worst - 1] {
+            simplex[worst] = reflected;
+            costs[worst] = reflected_cost;
+        } else {
+            let contracted = towards(if reflected_cost < costs[worst] { 0.5 } else { -0.5 });
+            let contracted_cost = cost_at(&contracted);
+            if contracted_cost < costs[worst].min(reflected_cost) {
+                simplex[worst] = contracted;
+                costs[worst] = contracted_cost;
+            } else {
+                // Shrink towards the best
+                for i in 1 .. simplex.len() {
+                    simplex[i] = simplex[i].iter().zip(simplex[0].iter()).map(|(x, b)| (x + b) / 2.0).collect();
+                    costs[i] = cost_at(&simplex[i]);
+                }
+            }
+        }
+    }
+
+    let best = (0 .. simplex.len()).min_by(|&a, &b| costs[a].partial_cmp(&costs[b]).unwrap()).unwrap();
+    let result = Optimized {
+        free: free.to_vec(),
+        best: simplex[best].iter().zip(scales.iter()).map(|(&x, scale)| scale.value(x)).collect(),
+        cost: costs[best],
+        history: history,
+    };
+    for (param, &value) in free.iter().zip(start.iter()) {
+        params.set(param, value);
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use parameters::builtin_defaults;
+    use functions::{Radiator, VentedRadiator};
+
+    #[test]
+    fn optimize_design() {
+        let P = builtin_defaults().unwrap();
+        let model = Model::PassiveRadiator;
+        let free = vec![P.Cab.clone(), P.Mmp.clone()];
+        let (Cab, Mmp) = (P.Cab.v(), P.Mmp.v());
+
+        // Find the box and radiator mass that gave a response, from a different start
+        let freqs = log_sweep(P.Fs.v() / 4.0, P.Fs.v() * 4.0, 40);
+        let target = freqs.iter().map(|&f| response_db(&P, model, f)).collect::<Vec<f64>>();
+        P.set(&P.Cab, Cab * 1.5);
+        P.set(&P.Mmp, Mmp * 0.7);
+        let goal = Goal::Target { freqs, target };
+        let found = optimize(&P, model, &free, &goal, 500);
+        assert_eq!((P.Cab.v(), P.Mmp.v()), (Cab * 1.5, Mmp * 0.7));
+        assert!(found.cost < 1e-6, "{}", found.cost);
+        assert!((found.best[0] / Cab - 1.0).abs() < 1e-2 && (found.best[1] / Mmp - 1.0).abs() < 1e-2);
+        assert!(found.history.windows(2).all(|pair| pair[1] <= pair[0]));
+
+        // Extending the response keeps within the ripple
+        let goal = Goal::Extension { ripple: 1.0 };
+        let before = design_cost(&P, model, &goal);
+        let found = optimize(&P, model, &free, &goal, 500);
+        assert!(found.cost <= before);
+        found.apply(&P);
+        let ripple = metrics(&Radiator(&P), &log_sweep(P.Fs.v() / 10.0, P.Fs.v() * 10.0, 200)).unwrap().ripple;
+        assert!(ripple < 1.1, "{}", ripple);
+    }
+
+    #[test]
+    fn extension_by_model() {
+        let P = builtin_defaults().unwrap();
+        let goal = Goal::Extension { ripple: 1.0 };
+        let sweep = log_sweep(P.Fs.v() / 10.0, P.Fs.v() * 10.0, 200);
+
+        // A radiator so light and large that the response never comes up to -3 dB in the sweep is
+        // the worst design, not the most extended one
+        let model = Model::PassiveRadiator;
+        let (Mmp, Sp) = (P.Mmp.v(), P.Sp.v());
+        P.set(&P.Mmp, P.Mmp.min());
+        P.set(&P.Sp, P.Sp.max());
+        let response = metrics(&Radiator(&P), &sweep).unwrap();
+        assert!(response.f3.is_none() && response.peak_db < -3.0);
+        let degenerate = design_cost(&P, model, &goal);
+        assert!(degenerate >= sweep[sweep.len() - 1].ln(), "{}", degenerate);
+        P.set(&P.Mmp, Mmp);
+        P.set(&P.Sp, Sp);
+
+        // The box and port of a vented design are moved for its own response
+        let free = free_params(&P, Model::Vented).unwrap();
+        let before = design_cost(&P, Model::Vented, &goal);
+        let found = optimize(&P, Model::Vented, &free, &goal, 500);
+        assert!(found.cost < before, "{} {}", found.cost, before);
+        found.apply(&P);
+        let response = metrics(&VentedRadiator(&P), &sweep).unwrap();
+        assert!(response.ripple < 1.1, "{}", response.ripple);
+        assert!(free_params(&P, Model::Bandpass4).is_none());
+    }
+}
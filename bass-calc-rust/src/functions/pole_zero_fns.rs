@@ -0,0 +1,187 @@
+//! Poles and zeros of a transfer function, and the second order sections they make up
+//!
+//! Roots are in rad/s, found by Durand-Kerner iteration on the polynomial with s scaled so its
+//! roots are near the unit circle
+
+use std::cmp::Ordering;
+use std::f64::consts::PI;
+use functions::{BassFnData, C64};
+
+/// Natural frequency in Hz and Q of a pair of poles. A single real pole left over from pairing
+/// has no Q
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Section {
+    pub freq: f64,
+    pub q: Option<f64>,
+}
+
+/// Finite poles and zeros. If any coefficient or root isn't finite, as when `Rms` is 0 and `Qs`
+/// is undefined, the rest are kept and `defined` is false
+pub struct PoleZero {
+    pub poles: Vec<C64>,
+    pub zeros: Vec<C64>,
+    pub defined: bool,
+}
+
+// Orders numbers with NaN after everything else, so sorting can't fail
+fn total_order(a: f64, b: f64) -> Ordering {
+    a.partial_cmp(&b).unwrap_or_else(|| a.is_nan().cmp(&b.is_nan()))
+}
+
+impl PoleZero {
+    /// Second order sections of the poles, in order of frequency. Complex poles pair with their
+    /// conjugates, and real poles with their nearest neighbour in frequency
+    pub fn sections(&self) -> Vec<Section> {
+        let mut sections = vec![];
+        let mut real = vec![];
+        for p in self.poles.iter() {
+            if p.im > 0.0 {
+                sections.push(Section { freq: p.norm() / (2.0 * PI), q: Some(p.norm() / (-2.0 * p.re)) });
+            } else if p.im == 0.0 {
+                real.push(p.re);
+            }
+        }
+        real.sort_by(|a, b| total_order(a.abs(), b.abs()));
+        for pair in real.chunks(2) {
+            sections.push(if pair.len() == 2 {
+                let ω0 = (pair[0] * pair[1]).sqrt();
+                Section { freq: ω0 / (2.0 * PI), q: Some(ω0 / -(pair[0] + pair[1])) }
+            } else {
+                Section { freq: pair[0].abs() / (2.0 * PI), q: None }
+            });
+        }
+        sections.sort_by(|a, b| total_order(a.freq, b.freq));
+        sections
+    }
+
+    /// Poles in the right half plane, which make the response grow without bound
+    pub fn unstable_poles(&self) -> Vec<C64> {
+        self.poles.iter().cloned().filter(|p| p.re > 1e-9 * p.norm()).collect()
+    }
+
+    /// Whether the response is defined and has no poles in the right half plane
+    pub fn is_stable(&self) -> bool {
+        self.defined && self.unstable_poles().is_empty()
+    }
+}
+
+/// Roots of the polynomial `p`, with coefficients highest power first
+pub fn roots(p: &[f64]) -> Vec<C64> {
+    let p = p.iter().cloned().skip_while(|x| *x == 0.0).collect::<Vec<f64>>();
+
+    // Roots at the origin come off the end
+    let at_origin = p.iter().rev().take_while(|x| **x == 0.0).count();
+    let p = &p[.. p.len() - at_origin];
+    let mut found = vec![C64::new(0.0, 0.0); at_origin];
+    let order = p.len().saturating_sub(1);
+    if order == 0 {
+        return found;
+    }
+
+    // Substitute s = scale z so the roots in z have a geometric mean magnitude of 1, and make the
+    // polynomial monic
+    let scale = (p[order] / p[0]).abs().powf(1.0 / order as f64);
+    let monic = p.iter().enumerate()
+        .map(|(i, x)| x / p[0] / scale.powi(i as i32))
+        .collect::<Vec<f64>>();
+    let at = |z: C64| monic.iter().fold(C64::new(0., 0.), |sum, a| sum * z + a);
+
+    let seed = C64::new(0.4, 0.9);
+    let mut z = vec![C64::new(1., 0.)];
+    for i in 1 .. order {
+        let next = z[i - 1] * seed;
+        z.push(next);
+    }
+    for _ in 0 .. 1000 {
+        let mut change: f64 = 0.0;
+        for i in 0 .. order {
+            let others = (0 .. order).filter(|&j| j != i).fold(C64::new(1., 0.), |prod, j| prod * (z[i] - z[j]));
+            let step = at(z[i]) / others;
+            z[i] -= step;
+            change = change.max(step.norm());
+        }
+        if change < 1e-14 {
+            break;
+        }
+    }
+
+    // Conjugate pairs come out with tiny imaginary parts left on real roots
+    found.extend(z.iter().map(|z| {
+        let root = z * scale;
+        if root.im.abs() < 1e-7 * root.norm() { C64::new(root.re, 0.0) } else { root }
+    }));
+    found
+}
+
+/// Poles and zeros of `data`, less any that cancel at the origin
+pub fn pole_zero(data: &BassFnData) -> PoleZero {
+    let at_origin = |p: &[f64]| p.iter().rev().take_while(|x| **x == 0.0).count();
+    let common = at_origin(&data.num).min(at_origin(&data.den));
+    let poles = roots(&data.den[.. data.den.len() - common]);
+    let zeros = roots(&data.num[.. data.num.len() - common]);
+
+    let is_finite = |root: &C64| root.re.is_finite() && root.im.is_finite();
+    let defined = data.num.iter().chain(data.den.iter()).all(|x| x.is_finite())
+        && poles.iter().chain(zeros.iter()).all(|root| is_finite(root));
+    PoleZero {
+        poles: poles.into_iter().filter(|root| is_finite(root)).collect(),
+        zeros: zeros.into_iter().filter(|root| is_finite(root)).collect(),
+        defined,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use functions::{Bandpass4Radiator, Radiator, SealedRadiator, VentedRadiator};
+    use parameters::builtin_defaults;
+
+    #[test]
+    fn pole_zero_sections() {
+        // (s + 1)(s + 2)(s^2 + s + 1) has two real roots and a pair with Q 1
+        let mut found = roots(&[1., 4., 6., 5., 2.]);
+        found.sort_by(|a, b| total_order(a.re, b.re).then(total_order(a.im, b.im)));
+        let expected = [C64::new(-2., 0.), C64::new(-1., 0.),
+                        C64::new(-0.5, -0.75f64.sqrt()), C64::new(-0.5, 0.75f64.sqrt())];
+        for (root, expected) in found.iter().zip(expected.iter()) {
+            assert!((root - expected).norm() < 1e-9, "{}", root);
+        }
+
+        // A sealed box is a single section at Fc and Qtc, with both zeros at the origin
+        let P = builtin_defaults().unwrap();
+        let sealed = pole_zero(&SealedRadiator(&P));
+        let sections = sealed.sections();
+        assert_eq!(sections.len(), 1);
+        assert!((sections[0].freq / P.Fc.v() - 1.0).abs() < 1e-9);
+        assert!((sections[0].q.unwrap() / P.Qtc.v() - 1.0).abs() < 1e-9);
+        assert!(sealed.zeros.iter().all(|z| z.norm() == 0.0));
+        assert!(sealed.is_stable());
+
+        // A vented box has four poles in two sections, and the bandpass boxes' common factor of s
+        // cancels out
+        let bandpass = pole_zero(&Bandpass4Radiator(&P));
+        assert!(bandpass.poles.iter().all(|p| p.norm() > 0.0));
+        let vented = pole_zero(&VentedRadiator(&P));
+        assert_eq!(vented.poles.len(), 4);
+        assert_eq!(vented.sections().len(), 2);
+        assert!(vented.is_stable());
+
+        // Negative damping puts a pair in the right half plane
+        let unstable = pole_zero(&BassFnData { num: vec![1.], den: vec![1., -1., 1.] });
+        assert_eq!(unstable.unstable_poles().len(), 2);
+        assert!(!unstable.is_stable());
+
+        // With no mechanical losses Qs is undefined, so are the roots
+        P.set(&P.Rms, 0.0);
+        let undefined = pole_zero(&Radiator(&P));
+        assert!(!undefined.defined && !undefined.is_stable());
+        assert!(undefined.poles.iter().all(|p| p.re.is_finite() && p.im.is_finite()));
+        let _ = undefined.sections();
+
+        // NaN sorts last
+        let mut values = vec![2.0, ::std::f64::NAN, 1.0];
+        values.sort_by(|a, b| total_order(*a, *b));
+        assert_eq!(&values[.. 2], &[1.0, 2.0]);
+        assert!(values[2].is_nan());
+    }
+}
@@ -0,0 +1,86 @@
+//! Equations from "Closed-Box Loudspeaker Systems, Part I: Analysis" by Richard H. Small
+//!
+//! The box is treated as lossless, so the system is the driver's second order high pass
+//! shifted up to `Fc` and `Qtc`
+
+use std::f64::consts::PI;
+use parameters::Parameters;
+use functions::BassFnData;
+
+// Time constant of the closed box resonance
+fn tc(params: &Parameters) -> f64 {
+    1.0 / (2.0 * PI * params.Fc.v())
+}
+
+/// Sound pressure of the closed box, a second order high pass at `Fc` with `Qtc`
+pub fn SealedRadiator(params: &Parameters) -> BassFnData {
+    let Tc = tc(params);
+    let Tc2 = Tc.powf(2.);
+    let Qtc = params.Qtc.v();
+
+    BassFnData {
+        num: vec![Tc2, 0., 0.],
+        den: vec![Tc2, Tc / Qtc, 1.]
+    }
+}
+
+/// Cone excursion relative to its static value in the box, where the air has already stiffened
+/// the suspension by `1 + α`
+pub fn SealedDisplacement(params: &Parameters) -> BassFnData {
+    let Tc = tc(params);
+    let Tc2 = Tc.powf(2.);
+    let Qtc = params.Qtc.v();
+
+    BassFnData {
+        num: vec![0., 0., 1.],
+        den: vec![Tc2, Tc / Qtc, 1.]
+    }
+}
+
+/// Electrical impedance over `Re + Rg`, a single peak at `Fc`. The box raises the mechanical Q
+/// by the same factor as Qtc, so the peak's width follows `Qms Qtc / Qs`
+pub fn SealedImpedance(params: &Parameters) -> BassFnData {
+    let Tc = tc(params);
+    let Tc2 = Tc.powf(2.);
+    let Qtc = params.Qtc.v();
+    let Qmc = params.Qms.v() * Qtc / params.Qs.v();
+
+    BassFnData {
+        num: vec![Tc2, Tc / Qtc, 1.],
+        den: vec![Tc2, Tc / Qmc, 1.]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use functions::bass_fn_point;
+    use parameters::builtin_defaults;
+
+    #[test]
+    fn sealed_response() {
+        let P = builtin_defaults().unwrap();
+        let Tc = tc(&P);
+        let Qtc = P.Qtc.v();
+
+        // -12 dB/octave below Fc, flat above, and Qtc at resonance
+        let radiator = SealedRadiator(&P);
+        assert!((bass_fn_point(&radiator, 1.0 / Tc) - Qtc).abs() < 1e-9);
+        assert!((bass_fn_point(&radiator, 1000.0 / Tc) - 1.0).abs() < 1e-5);
+        assert!((bass_fn_point(&radiator, 0.01 / Tc) - 1e-4).abs() < 1e-6);
+
+        // Impedance peaks at Re * (1 + Qms / Qes)
+        let impedance = SealedImpedance(&P);
+        let peak = P.Qms.v() / P.Qes.v() + 1.0;
+        assert!((bass_fn_point(&impedance, 1.0 / Tc) - peak).abs() < 1e-9);
+        assert!((bass_fn_point(&impedance, 1e-6 / Tc) - 1.0).abs() < 1e-6);
+
+        // Rg adds to Re and damps the driver less, raising Qtc and lowering the peak
+        P.set(&P.Rg, P.Re.v());
+        assert!((P.Qe.v() - 2.0 * P.Qes.v()).abs() < 1e-12);
+        assert!(P.Qtc.v() > Qtc);
+        let impedance = SealedImpedance(&P);
+        let peak = P.Qms.v() / P.Qe.v() + 1.0;
+        assert!((bass_fn_point(&impedance, 1.0 / tc(&P)) - peak).abs() < 1e-9);
+    }
+}
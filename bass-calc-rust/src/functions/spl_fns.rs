@@ -0,0 +1,103 @@
+//! Absolute sound pressure level, from the reference efficiency `η0` and the drive level
+//!
+//! The driver radiates into half space, as on an infinite baffle. Electrical input power is
+//! `Pin = Vin^2 / (Re + Rg)`, the power drawn from the source, which is what `η0` is relative to.
+//! The voice coil inductance shapes the response, but doesn't change the nominal input power
+
+use std::f64::consts::PI;
+use parameters::Parameters;
+use functions::{Model, SemiInductance, bode, electrical_response};
+
+/// Reference pressure of dB SPL, in Pa
+pub const P_REF: f64 = 20e-6;
+
+/// Drive voltage of the sensitivity figure, 1 W into 8 ohms
+pub const SENSITIVITY_VOLTS: f64 = 2.83;
+
+/// SPL in dB at `distance` m from a source radiating `acoustic_power` W into half space
+pub fn acoustic_spl(params: &Parameters, acoustic_power: f64, distance: f64) -> f64 {
+    let ρ0 = params.ρ0.v();
+    let c = params.c.v();
+    let intensity = acoustic_power / (2.0 * PI * distance.powi(2));
+    10.0 * (ρ0 * c * intensity / P_REF.powi(2)).log10()
+}
+
+/// Passband SPL in dB for `power` W of electrical input, at `distance` m
+pub fn passband_spl(params: &Parameters, power: f64, distance: f64) -> f64 {
+    acoustic_spl(params, params.η0.v() * power, distance)
+}
+
+/// Passband SPL at the drive level `Pin` and distance `r`
+pub fn drive_spl(params: &Parameters) -> f64 {
+    passband_spl(params, params.Pin.v(), params.r.v())
+}
+
+/// Passband SPL at 2.83 V from the source and 1 m
+pub fn sensitivity(params: &Parameters) -> f64 {
+    passband_spl(params, SENSITIVITY_VOLTS.powi(2) / (params.Re.v() + params.Rg.v()), 1.0)
+}
+
+/// Passband SPL for 1 W from the source at 1 m, in dB/W/m
+pub fn sensitivity_per_watt(params: &Parameters) -> f64 {
+    passband_spl(params, 1.0, 1.0)
+}
+
+/// Response of `model` relative to its passband over `freqs` in Hz, in dB, including the voice
+/// coil inductance and `Rg`
+pub fn response_db(params: &Parameters, model: Model, inductance: SemiInductance, freqs: &[f64]) -> Vec<f64> {
+    let response = bode(&(model.response())(params), freqs).response;
+    response.iter().zip(electrical_response(params, model, inductance, freqs).iter())
+        .map(|(h, e)| 20.0 * (h * e).norm().log10())
+        .collect()
+}
+
+/// SPL in dB of `model` over `freqs` in Hz, at the drive level `Pin` and distance `r`
+pub fn spl(params: &Parameters, model: Model, inductance: SemiInductance, freqs: &[f64]) -> Vec<f64> {
+    let passband = drive_spl(params);
+    response_db(params, model, inductance, freqs).iter().map(|db| passband + db).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use functions::EfficiencyAdams;
+    use parameters::builtin_defaults;
+
+    #[test]
+    fn absolute_spl() {
+        let P = builtin_defaults().unwrap();
+
+        // The Thiele-Small and Adams forms of the reference efficiency agree
+        assert!((P.η0.v() / EfficiencyAdams(&P) - 1.0).abs() < 1e-9);
+
+        // 1 acoustic watt at 1 m in half space is about 112 dB
+        assert!((acoustic_spl(&P, 1.0, 1.0) - 112.1).abs() < 0.05);
+
+        // 2.83 V is 1 W into 8 ohms, twice the distance is 6 dB down and 10 times the power is 10 dB up
+        P.set(&P.Re, 8.0);
+        P.set(&P.Vin, SENSITIVITY_VOLTS);
+        assert!((drive_spl(&P) - passband_spl(&P, 1.0, 1.0)).abs() < 0.01);
+        assert!((sensitivity(&P) - drive_spl(&P)).abs() < 1e-12);
+        assert!((sensitivity(&P) - sensitivity_per_watt(&P)).abs() < 0.01);
+        P.set(&P.r, 2.0);
+        assert!((sensitivity(&P) - drive_spl(&P) - 20.0 * 2f64.log10()).abs() < 1e-9);
+        P.solve(&P.Pin, "Vin", 10.0 * P.Pin.v()).unwrap();
+        assert!((drive_spl(&P) - sensitivity(&P) + 20.0 * 2f64.log10() - 10.0).abs() < 1e-9);
+
+        // Rg equal to Re halves the voltage across the driver, so the output is 6 dB down
+        let before = drive_spl(&P);
+        P.set(&P.Rg, P.Re.v());
+        assert!((before - drive_spl(&P) - 20.0 * 2f64.log10()).abs() < 1e-9);
+        P.set(&P.Rg, 0.0);
+
+        // A sealed box reaches the passband level well above Fc, until the voice coil inductance
+        // rolls it off
+        let Fc = P.Fc.v();
+        let inductive = spl(&P, Model::Sealed, SemiInductance::None, &[Fc * 1000.0]);
+        P.set(&P.Le, 0.0);
+        let levels = spl(&P, Model::Sealed, SemiInductance::None, &[Fc / 10.0, Fc * 1000.0]);
+        assert!((levels[1] - drive_spl(&P)).abs() < 1e-3);
+        assert!(levels[0] < levels[1] - 30.0);
+        assert!(inductive[0] < levels[1] - 6.0);
+    }
+}
@@ -0,0 +1,194 @@
+//! Time domain response of a transfer function, in place of the prototype's `odeint`
+//!
+//! The transfer function is realised in controllable canonical form and integrated with fourth
+//! order Runge-Kutta. Times are in seconds, starting from rest at t = 0
+//!
+//! There is no response for a transfer function that can't be realised this way: one with no
+//! denominator, as when `Mmd` or `Cms` is 0, a numerator of higher degree than the denominator, or
+//! coefficients that aren't finite
+
+use std::f64::consts::PI;
+use functions::BassFnData;
+
+// State space form of a transfer function: x' = A x + B u, y = C x + D u, with A the companion
+// matrix of the denominator and B the first unit vector
+struct StateSpace {
+    /// Denominator coefficients after the leading one, which is normalised to 1
+    a: Vec<f64>,
+    c: Vec<f64>,
+    d: f64,
+    /// Longest step that keeps the integration accurate for the fastest pole
+    max_step: f64,
+}
+
+impl StateSpace {
+    // `None` unless the denominator has a nonzero coefficient and is of at least the numerator's
+    // degree, and all the coefficients are finite
+    fn new(data: &BassFnData) -> Option<StateSpace> {
+        let den = data.den.iter().cloned().skip_while(|x| *x == 0.0).collect::<Vec<f64>>();
+        let num = data.num.iter().cloned().skip_while(|x| *x == 0.0).collect::<Vec<f64>>();
+        let finite = den.iter().chain(num.iter()).all(|x| x.is_finite());
+        if den.is_empty() || num.len() > den.len() || !finite {
+            return None;
+        }
+        let order = den.len() - 1;
+        let a = den.iter().skip(1).map(|x| x / den[0]).collect::<Vec<f64>>();
+        let mut b = vec![0.; den.len() - num.len()];
+        b.extend(num.iter().map(|x| x / den[0]));
+
+        // Fujiwara's bound on the magnitude of the poles
+        let fastest = a.iter().enumerate()
+            .map(|(i, x)| x.abs().powf(1.0 / (i + 1) as f64))
+            .fold(0.0, f64::max) * 2.0;
+
+        Some(StateSpace {
+            c: (0 .. order).map(|i| b[i + 1] - b[0] * a[i]).collect(),
+            d: b[0],
+            a: a,
+            max_step: 0.1 / fastest,
+        })
+    }
+
+    fn derivative(&self, x: &[f64], u: f64) -> Vec<f64> {
+        let mut dx = vec![u - self.a.iter().zip(x.iter()).map(|(a, x)| a * x).sum::<f64>()];
+        dx.extend_from_slice(&x[.. x.len().saturating_sub(1)]);
+        dx
+    }
+
+    fn output(&self, x: &[f64], u: f64) -> f64 {
+        self.c.iter().zip(x.iter()).map(|(c, x)| c * x).sum::<f64>() + self.d * u
+    }
+
+    // Output at each of `times`, in increasing order, for the input `u` and initial state `x`
+    fn simulate<F: Fn(f64) -> f64>(&self, mut x: Vec<f64>, u: F, times: &[f64]) -> Vec<f64> {
+        let add = |x: &[f64], dx: &[f64], h: f64| {
+            x.iter().zip(dx.iter()).map(|(x, dx)| x + h * dx).collect::<Vec<f64>>()
+        };
+        let mut t = 0.0;
+        let mut output = Vec::with_capacity(times.len());
+        for &end in times {
+            let steps = ((end - t) / self.max_step).ceil().max(1.0);
+            let h = (end - t) / steps;
+            for _ in 0 .. steps as usize {
+                let k1 = self.derivative(&x, u(t));
+                let k2 = self.derivative(&add(&x, &k1, h / 2.0), u(t + h / 2.0));
+                let k3 = self.derivative(&add(&x, &k2, h / 2.0), u(t + h / 2.0));
+                let k4 = self.derivative(&add(&x, &k3, h), u(t + h));
+                for i in 0 .. x.len() {
+                    x[i] += h / 6.0 * (k1[i] + 2.0 * k2[i] + 2.0 * k3[i] + k4[i]);
+                }
+                t += h;
+            }
+            output.push(self.output(&x, u(t)));
+        }
+        output
+    }
+}
+
+/// `points` times from 0 to `duration` inclusive, evenly spaced
+pub fn time_sweep(duration: f64, points: usize) -> Vec<f64> {
+    let step = duration / (points.max(2) - 1) as f64;
+    (0 .. points).map(|i| step * i as f64).collect()
+}
+
+/// Response of `data` over `times` to a unit step at t = 0
+pub fn step_response(data: &BassFnData, times: &[f64]) -> Option<Vec<f64>> {
+    let system = StateSpace::new(data)?;
+    Some(system.simulate(vec![0.; system.a.len()], |_| 1.0, times))
+}
+
+/// Response of `data` over `times` to a unit impulse at t = 0, in 1/s. A transfer function that
+/// is flat at high frequencies also passes the impulse itself straight through, which is left out
+pub fn impulse_response(data: &BassFnData, times: &[f64]) -> Option<Vec<f64>> {
+    let system = StateSpace::new(data)?;
+    let mut x = vec![0.; system.a.len()];
+    if let Some(first) = x.first_mut() {
+        *first = 1.0;
+    }
+    Some(system.simulate(x, |_| 0.0, times))
+}
+
+/// Unit sine burst of `cycles` cycles at `freq` Hz, starting at t = 0
+pub fn tone_burst(freq: f64, cycles: f64, t: f64) -> f64 {
+    if t < 0.0 || t > cycles / freq {
+        0.0
+    } else {
+        (2.0 * PI * freq * t).sin()
+    }
+}
+
+/// Response of `data` over `times` to `tone_burst`
+pub fn tone_burst_response(data: &BassFnData, freq: f64, cycles: f64, times: &[f64]) -> Option<Vec<f64>> {
+    let system = StateSpace::new(data)?;
+    Some(system.simulate(vec![0.; system.a.len()], |t| tone_burst(freq, cycles, t), times))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use functions::{SealedDisplacement, SealedRadiator, bass_fn_complex};
+    use parameters::builtin_defaults;
+
+    #[test]
+    fn first_order() {
+        // 1 / (s T + 1) charges with time constant T
+        let T = 0.01;
+        let data = BassFnData { num: vec![0., 1.], den: vec![T, 1.] };
+        let times = time_sweep(0.05, 11);
+        let step = step_response(&data, &times).unwrap();
+        let impulse = impulse_response(&data, &times).unwrap();
+        for (i, &t) in times.iter().enumerate() {
+            assert!((step[i] - (1.0 - (-t / T).exp())).abs() < 1e-6);
+            assert!((impulse[i] - (-t / T).exp() / T).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn unrealisable() {
+        let times = time_sweep(0.05, 11);
+        let improper = BassFnData { num: vec![1., 0.], den: vec![0., 1.] };
+        assert!(step_response(&improper, &times).is_none());
+        let no_den = BassFnData { num: vec![0., 1.], den: vec![0., 0.] };
+        assert!(impulse_response(&no_den, &times).is_none());
+        let infinite = BassFnData { num: vec![1.], den: vec![::std::f64::INFINITY, 1.] };
+        assert!(tone_burst_response(&infinite, 100.0, 4.0, &times).is_none());
+
+        // With no moving mass, not even the air load, the resonance is infinite and there is no
+        // response
+        let P = builtin_defaults().unwrap();
+        P.set(&P.Mmd, 0.0);
+        P.set(&P.ρ0, 0.0);
+        assert!(step_response(&SealedRadiator(&P), &times).is_none());
+    }
+
+    #[test]
+    fn sealed_transients() {
+        let P = builtin_defaults().unwrap();
+        let Fc = P.Fc.v();
+        let Tc = 1.0 / (2.0 * PI * Fc);
+        let Qtc = P.Qtc.v();
+        let times = time_sweep(100.0 / Fc, 1001);
+
+        // A high pass follows the edge of a step and then settles back to zero, while the cone
+        // settles at its static displacement
+        let radiator = SealedRadiator(&P);
+        let step = step_response(&radiator, &times).unwrap();
+        assert!((step[0] - 1.0).abs() < 1e-12);
+        assert!(step[1000].abs() < 1e-3);
+        let displacement = step_response(&SealedDisplacement(&P), &times).unwrap();
+        assert!((displacement[1000] - 1.0).abs() < 1e-3);
+
+        // Apart from the impulse passed straight through, the response starts at -1 / (Tc Qtc)
+        let impulse = impulse_response(&radiator, &times).unwrap();
+        assert!((impulse[0] * Tc * Qtc + 1.0).abs() < 1e-9);
+
+        // Once the start has settled, the burst comes through with the steady state gain and phase
+        let freq = 20.0 * Fc;
+        let h = bass_fn_complex(&radiator, 2.0 * PI * freq);
+        let burst = tone_burst_response(&radiator, freq, 40.0, &times).unwrap();
+        for (i, &t) in times.iter().enumerate().filter(|&(_, &t)| t > 1.0 / Fc && t < 1.9 / Fc) {
+            let steady = h.norm() * (2.0 * PI * freq * t + h.arg()).sin();
+            assert!((burst[i] - steady).abs() < 0.01);
+        }
+    }
+}
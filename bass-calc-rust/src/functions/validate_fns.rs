@@ -4,23 +4,22 @@
 use parameters::Parameters;
 use functions::graph_fns::BassFnData;
 
-use uom::si::time::second;
-use uom::si::f64::Time;
+use parameters::units::{dim, Time};
 
 #[allow(dead_code)]
 fn Ts(params: &Parameters) -> Time {
-    Time::new::<second>(params.Ts.v())
+    params.Ts.q()
 }
 
 #[allow(dead_code)]
 fn Tp(params: &Parameters) -> Time {
-    Time::new::<second>(params.Tp.v())
+    params.Tp.q()
 }
 
 /// Reduced version of Hurlburt
 #[allow(dead_code)]
 pub fn ValidateRadiatorTest(params: &Parameters) -> BassFnData {
-    let g =  0.2; // τb / Ts 0.2 is a good guesstimate
+    let g = params.Γ.v(); // τb / Ts
     let α = params.α.v();
     let δ = params.δ.v();
     let psi = α + δ + 1.0;
@@ -57,7 +56,7 @@ pub fn ValidateRadiatorTest(params: &Parameters) -> BassFnData {
 
 #[allow(dead_code)]
 fn ValidateRadiator(params: &Parameters) -> BassFnData {
-    let g =  0.2; // τb / Ts 0.2 is a good guesstimate
+    let g = params.Γ.v(); // τb / Ts
     let α = params.α.v();
     let δ = params.δ.v();
     let psi = α + δ + 1.0;
@@ -111,7 +110,7 @@ mod test {
 
     #[test]
     fn radiator_alt() {
-        let params = builtin_defaults();
+        let params = builtin_defaults().unwrap();
         let d1 = ValidateRadiator(&params);
         let d2 = Radiator(&params);
         let d3 = ValidateRadiatorTest(&params);
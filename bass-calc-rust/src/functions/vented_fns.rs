@@ -0,0 +1,101 @@
+//! Equations from "Vented-Box Loudspeaker Systems, Part I: Small-Signal Analysis" by Richard H. Small
+//!
+//! The box tuning is `Fv`, set by the port geometry. Box losses are all lumped into the leakage
+//! term, using the combined box loss `QB` at `Fv`. `Γ` is the passive radiator's loss, relative to
+//! `Fb`, and isn't used here
+
+use std::f64::consts::PI;
+use parameters::Parameters;
+use functions::BassFnData;
+
+// Denominator shared by every vented box transfer function, for a driver with total Q `Q`
+fn vented_den(params: &Parameters, Q: f64) -> Vec<f64> {
+    let α = params.α.v();
+    let Ts = params.Ts.v();
+    let Ts2 = Ts.powf(2.);
+    let Tb = 1.0 / (2.0 * PI * params.Fv.v());
+    let Tb2 = Tb.powf(2.);
+    let QB = params.QB.v();
+
+    let a4 = Tb2 * Ts2;
+    let a3 = Tb2 * Ts / Q + Tb * Ts2 / QB;
+    let a2 = (α + 1.) * Tb2 + Tb * Ts / (QB * Q) + Ts2;
+    let a1 = Tb / QB + Ts / Q;
+
+    vec![a4, a3, a2, a1, 1.]
+}
+
+/// Sound pressure of the driver and port together, a fourth order high pass tuned by `Fv`
+pub fn VentedRadiator(params: &Parameters) -> BassFnData {
+    let den = vented_den(params, params.Qs.v());
+
+    BassFnData {
+        num: vec![den[0], 0., 0., 0., 0.],
+        den: den
+    }
+}
+
+/// Cone excursion relative to its value at DC, which falls to a minimum at `Fv` where the port
+/// does the work
+pub fn VentedDisplacement(params: &Parameters) -> BassFnData {
+    let Tb = 1.0 / (2.0 * PI * params.Fv.v());
+    let Tb2 = Tb.powf(2.);
+    let QB = params.QB.v();
+
+    BassFnData {
+        num: vec![0., 0., Tb2, Tb / QB, 1.],
+        den: vented_den(params, params.Qs.v())
+    }
+}
+
+/// Excursion of the air in the ports, relative to the cone excursion at DC. The ports are
+/// narrower than the cone, so the air moves further by `Sd / (Nv Sv)`
+pub fn PortDisplacement(params: &Parameters) -> BassFnData {
+    let area_ratio = params.Sd.v() / (params.Nv.v() * params.Sv.v());
+
+    BassFnData {
+        num: vec![0., 0., 0., 0., area_ratio],
+        den: vented_den(params, params.Qs.v())
+    }
+}
+
+/// Electrical impedance over `Re + Rg`, with a peak either side of `Fv` and the minimum between
+/// them near the tuning
+pub fn VentedImpedance(params: &Parameters) -> BassFnData {
+    BassFnData {
+        num: vented_den(params, params.Qs.v()),
+        den: vented_den(params, params.Qms.v())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use functions::bass_fn_point;
+    use parameters::builtin_defaults;
+
+    #[test]
+    fn vented_response() {
+        let P = builtin_defaults().unwrap();
+        // Box the size of Vas
+        P.set(&P.Cab, P.Cas.v());
+        let ωv = 2.0 * PI * P.Fv.v();
+
+        // -24 dB/octave well below tuning, flat well above
+        let radiator = VentedRadiator(&P);
+        assert!((bass_fn_point(&radiator, 1000.0 * ωv) - 1.0).abs() < 1e-3);
+        let low = bass_fn_point(&radiator, 0.01 * ωv);
+        assert!((bass_fn_point(&radiator, 0.005 * ωv) / low - 1.0 / 16.0).abs() < 1e-3);
+
+        // The port does the work at tuning, so cone excursion dips
+        let cone = bass_fn_point(&VentedDisplacement(&P), ωv);
+        let port = bass_fn_point(&PortDisplacement(&P), ωv);
+        assert!(cone < bass_fn_point(&VentedDisplacement(&P), 0.7 * ωv));
+        assert!(port > cone);
+
+        // Impedance is Re at DC and has a minimum near tuning between two peaks
+        let impedance = VentedImpedance(&P);
+        assert!((bass_fn_point(&impedance, 1e-6 * ωv) - 1.0).abs() < 1e-6);
+        assert!(bass_fn_point(&impedance, ωv) < bass_fn_point(&impedance, 0.5 * ωv));
+    }
+}
@@ -0,0 +1,161 @@
+//! Electrical side of the driver: voice coil inductance, and the series resistance `Rg` of the
+//! amplifier and cable
+//!
+//! `Rg` is part of the models through the effective electrical Q, `Qe`. Their transfer functions
+//! still assume a purely resistive voice coil, so the responses are scaled by the ratio of the
+//! ideal to the actual current once the inductance is in series. Only the sweeps here and in
+//! `spl_fns` and `excursion_fns` include it. The time responses, poles and zeros, metrics and
+//! optimizer work from the transfer functions alone
+
+use std::f64::consts::PI;
+use parameters::Parameters;
+use functions::{Model, BassFnData, C64, bass_fn_complex, bode, response_db};
+
+/// Model of the voice coil's lossy inductance
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SemiInductance {
+    /// `Le` alone
+    None,
+    /// `Le` followed by `L2` and `R2` in parallel
+    LR2,
+    /// Wright's empirical fit, `Krm * w^Erm + j Kxm * w^Exm`, in place of `Le`
+    Wright,
+}
+
+impl SemiInductance {
+    pub const ALL: [SemiInductance; 3] = [SemiInductance::None, SemiInductance::LR2, SemiInductance::Wright];
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            SemiInductance::None => "Le only",
+            SemiInductance::LR2 => "LR-2",
+            SemiInductance::Wright => "Wright",
+        }
+    }
+}
+
+/// Impedance of the voice coil in ohm at angular frequency `w`, apart from `Re`
+pub fn voice_coil_impedance(params: &Parameters, inductance: SemiInductance, w: f64) -> C64 {
+    let s = C64::new(0.0, w);
+    let sLe = s * params.Le.v();
+    match inductance {
+        SemiInductance::None => sLe,
+        SemiInductance::LR2 => {
+            let sL2 = s * params.L2.v();
+            let R2 = params.R2.v();
+            sLe + sL2 * R2 / (sL2 + R2)
+        },
+        SemiInductance::Wright => {
+            C64::new(params.Krm.v() * w.powf(params.Erm.v()), params.Kxm.v() * w.powf(params.Exm.v()))
+        },
+    }
+}
+
+// Impedance of `model` as its transfer function gives it, Rg and Re plus the motional impedance
+fn ideal_impedance(params: &Parameters, model: Model, w: f64) -> C64 {
+    bass_fn_complex(&(model.impedance())(params), w) * (params.Re.v() + params.Rg.v())
+}
+
+/// Impedance in ohm at the driver's terminals over `freqs` in Hz
+pub fn electrical_impedance(params: &Parameters, model: Model, inductance: SemiInductance,
+        freqs: &[f64]) -> Vec<C64> {
+    let Rg = params.Rg.v();
+    freqs.iter().map(|f| 2.0 * PI * f)
+        .map(|w| ideal_impedance(params, model, w) - Rg + voice_coil_impedance(params, inductance, w))
+        .collect()
+}
+
+/// Change in the response of `model` over `freqs` in Hz, from the voice coil inductance
+pub fn electrical_response(params: &Parameters, model: Model, inductance: SemiInductance,
+        freqs: &[f64]) -> Vec<C64> {
+    freqs.iter().map(|f| 2.0 * PI * f)
+        .map(|w| {
+            let ideal = ideal_impedance(params, model, w);
+            ideal / (ideal + voice_coil_impedance(params, inductance, w))
+        })
+        .collect()
+}
+
+/// Response of `model` driven by a current source. There is no electrical damping, so `Qe` is
+/// infinite and `Qs` becomes `Qms`, and the voice coil inductance has no effect. The impedance's
+/// denominator is the response's with `Qms` in place of `Qs`, so that is the denominator here
+pub fn current_response(params: &Parameters, model: Model) -> BassFnData {
+    BassFnData {
+        num: (model.response())(params).num,
+        den: (model.impedance())(params).den,
+    }
+}
+
+/// Change in dB of the response of `model` over `freqs` in Hz, from driving it with a current
+/// source instead of a voltage source behind `Rg`
+pub fn current_drive_db(params: &Parameters, model: Model, inductance: SemiInductance,
+        freqs: &[f64]) -> Vec<f64> {
+    let current = bode(&current_response(params, model), freqs).magnitude_db();
+    current.iter().zip(response_db(params, model, inductance, freqs).iter())
+        .map(|(current, voltage)| current - voltage)
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use parameters::builtin_defaults;
+
+    #[test]
+    fn voice_coil() {
+        let P = builtin_defaults().unwrap();
+        let Fs = P.Fs.v();
+        let Re = P.Re.v();
+        let Le = P.Le.v();
+        let high = [1000.0 * Fs];
+        let w = 2.0 * PI * high[0];
+
+        // Well above resonance the impedance is Re in series with Le
+        let inductance = SemiInductance::None;
+        let Z = electrical_impedance(&P, Model::Sealed, inductance, &high)[0];
+        assert!((Z - C64::new(Re, w * Le)).norm() / Z.norm() < 1e-3);
+        let response = electrical_response(&P, Model::Sealed, inductance, &high)[0];
+        assert!((response - Re / Z).norm() < 1e-3);
+
+        // LR-2 adds R2 in series at high frequencies, and L2 in series at low ones
+        let Zvc = voice_coil_impedance(&P, SemiInductance::LR2, w);
+        assert!((Zvc.re - P.R2.v()).abs() / P.R2.v() < 1e-2);
+        let w_low = P.R2.v() / P.L2.v() / 1e4;
+        let Zvc = voice_coil_impedance(&P, SemiInductance::LR2, w_low);
+        assert!((Zvc.im / w_low - (Le + P.L2.v())).abs() / Le < 1e-3);
+
+        // Wright's model follows its power laws
+        let Zvc = voice_coil_impedance(&P, SemiInductance::Wright, w);
+        assert!((Zvc.re - P.Krm.v() * w.powf(P.Erm.v())).abs() < 1e-12);
+        assert!((Zvc.im - P.Kxm.v() * w.powf(P.Exm.v())).abs() < 1e-12);
+
+        // Rg is outside the driver's terminals, and is already part of the transfer functions
+        P.set(&P.Rg, Re);
+        let low = [Fs / 1e6];
+        assert!((electrical_impedance(&P, Model::Vented, inductance, &low)[0] - C64::new(Re, 0.0)).norm() < 1e-6);
+        assert!((electrical_response(&P, Model::Vented, inductance, &low)[0] - C64::new(1.0, 0.0)).norm() < 1e-6);
+
+        // A current source leaves the sealed box with the driver's mechanical damping alone
+        let Fc = P.Fc.v();
+        let Qmc = P.Qms.v() * (1.0 + P.α.v()).sqrt();
+        let current = bode(&current_response(&P, Model::Sealed), &[Fc]).magnitude_db();
+        assert!((current[0] - 20.0 * Qmc.log10()).abs() < 1e-3);
+        let voltage = response_db(&P, Model::Sealed, inductance, &[Fc]);
+        assert!((current_drive_db(&P, Model::Sealed, inductance, &[Fc])[0] - (current[0] - voltage[0])).abs() < 1e-9);
+
+        // Every model's impedance numerator is its response denominator, so its denominator is the
+        // response with Qms in place of Qs. Making Qes huge comes to the same thing
+        let freqs = [Fs / 3.0, Fs, Fs * 3.0];
+        for model in Model::ALL.iter().cloned() {
+            let response = (model.response())(&P);
+            assert_eq!((model.impedance())(&P).num, response.den, "{}", model.name());
+            let current = bode(&current_response(&P, model), &freqs).magnitude_db();
+            P.set(&P.Bl, P.Bl.v() * 1e-6);
+            let weak = bode(&(model.response())(&P), &freqs).magnitude_db();
+            P.set(&P.Bl, P.Bl.v() * 1e6);
+            for (c, w) in current.iter().zip(weak.iter()) {
+                assert!((c - w).abs() < 1e-3, "{}", model.name());
+            }
+        }
+    }
+}
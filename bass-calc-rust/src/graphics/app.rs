@@ -1,24 +1,125 @@
 
 use conrod::{Ui, UiCell};
 use std::f64;
-use graphics::{App, AppInterface, BassGraph};
-use functions::Radiator;
-use parameters::{Param, Parameters};
-
-use conrod::{color, widget, Colorable, Positionable, Sizeable, Widget};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use graphics::{App, AppInterface, BassGraph, Plot, PoleZeroPlot};
+use functions::{align, free_params, model_metrics, optimize, Alignment, AlignmentFit, Goal, Model, SemiInductance};
+use functions::{efficiency_constant, pole_zero, sensitivity, sensitivity_per_watt, SENSITIVITY_VOLTS};
+use parameters::{builtin_defaults, library, load_lenient, Param, Parameters, Preset, save_file};
+
+use conrod::{color, widget, Colorable, Labelable, Positionable, Sizeable, Widget};
 use conrod::color::rgb;
-use conrod::widget::{id, Id, Canvas, Slider, Rectangle, Scrollbar, Tabs, Text, TextEdit};
+use conrod::widget::{button, id, Id, Button, Canvas, DropDownList, List, Slider, Rectangle, Scrollbar, Tabs, Text, TextEdit};
+
+/// Highest peak in dB that Optimize allows in the passband
+const OPTIMIZE_RIPPLE: f64 = 1.0;
+const OPTIMIZE_ITERATIONS: usize = 500;
+
+/// Lines summarising the response under the graph, for the parameter values and model they were
+/// worked out for
+struct Summary {
+    value_bits: Vec<u64>,
+    model: Model,
+    sensitivity: String,
+    sections: String,
+    metrics: String,
+    efficiency: String,
+}
+
+impl Summary {
+    fn new(params: &Parameters, model: Model) -> Summary {
+        let sensitivity = format!("Sensitivity {:.1} dB at {} V / 1 m", sensitivity(params), SENSITIVITY_VOLTS);
+
+        // Second order sections of the alignment, and a warning if it would be unstable. These and
+        // the metrics are of the transfer function, without the voice coil inductance
+        let roots = pole_zero(&(model.response())(params));
+        let mut sections = roots.sections().iter()
+            .map(|section| match section.q {
+                Some(q) => format!("{:.1} Hz Q {:.2}", section.freq, q),
+                None => format!("{:.1} Hz", section.freq),
+            })
+            .collect::<Vec<String>>()
+            .join(", ");
+        if !roots.defined {
+            sections.push_str(" - undefined, the response isn't finite");
+        } else if !roots.is_stable() {
+            sections.push_str(" - unstable, poles in the right half plane");
+        }
+
+        let response = model_metrics(params, model);
+        let metrics = format!("F3 {}  F6 {}  F10 {}  Peak {:.1} dB at {:.1} Hz  Ripple {:.1} dB",
+            format_freq(response.f3), format_freq(response.f6), format_freq(response.f10),
+            response.peak_db, response.peak_freq, response.ripple);
+
+        // Small's efficiency constant relates η0 to the F3 and box size of this response
+        let efficiency_bandwidth = efficiency_constant(params, model)
+            .map_or("-".to_string(), |kη| format!("{:.1}", kη));
+        let efficiency = format!("η0 {:.2} %  {:.1} dB/W/m  Efficiency-bandwidth kη {}",
+            100.0 * params.η0.v(), sensitivity_per_watt(params), efficiency_bandwidth);
+
+        Summary {
+            value_bits: params.value_bits(),
+            model,
+            sensitivity,
+            sections: format!("Poles {}", sections),
+            metrics,
+            efficiency,
+        }
+    }
+}
 
 pub struct BassCalcApp {
     ids: Option<Ids>,
     title_ids: Vec<[Id; 3]>,
-    param_ids: Vec<Vec<[Id; 6]>>,
+    param_ids: Vec<Vec<[Id; 7]>>,
     params: Parameters,
+    // Index into `Parameters::inversions` of the input adjusted when a derived parameter is edited
+    solve_inputs: RefCell<HashMap<String, usize>>,
+    // Problem with the last edit of each parameter, shown next to its entry
+    entry_errors: RefCell<HashMap<String, String>>,
+    // Preset file that Save writes to
+    preset_path: RefCell<Option<PathBuf>>,
+    save_as_text: RefCell<String>,
+    status: RefCell<String>,
+    // Directories scanned for presets, and the presets found in them
+    preset_dirs: Vec<PathBuf>,
+    presets: RefCell<Vec<Preset>>,
+    selected_preset: Cell<Option<usize>>,
+    preset_name_text: RefCell<String>,
+    // Enclosure type shown in the graph
+    model: Cell<Model>,
+    // Voice coil inductance model for the SPL, excursion and impedance plots
+    inductance: Cell<SemiInductance>,
+    // Part of the response shown in the graph
+    plot: Cell<Plot>,
+    // Passive radiator alignment the box is fitted to
+    alignment: Cell<Alignment>,
+    // Problems loading the last preset, shown until dismissed
+    load_problems: RefCell<Vec<String>>,
+    // Summary under the graph, worked out again only when the parameters or model change
+    summary: RefCell<Option<Summary>>,
 }
 
 widget_ids! {
     pub struct Ids {
         root,
+        toolbar,
+        save_button,
+        save_as_path,
+        save_as_button,
+        status,
+        model_select,
+        inductance_select,
+        plot_select,
+        alignment_select,
+        alignment_apply,
+        optimize_button,
+        problems,
+        problems_text,
+        problems_dismiss,
         body,
         param_column,
         param_tabs,
@@ -27,28 +128,489 @@ widget_ids! {
         tab_driver_label,
         tab_driver_list,
         tab_driver_list_top,
+        tab_presets,
+        preset_panel,
+        preset_name,
+        preset_duplicate,
+        preset_rename,
+        preset_delete,
+        preset_refresh,
+        preset_list,
         tab_graph,
         tab_graph_label,
         graph_column,
         graph_grid,
         graph,
+        pole_zero,
+        sensitivity,
+        sections,
+        metrics,
+        efficiency,
     }
 }
 
-pub fn make_app(params: Parameters) -> App<BassCalcApp> {
-    let app_data = BassCalcApp::new(params);
+pub fn make_app(params: Parameters, preset_path: Option<PathBuf>, load_problems: Vec<String>)
+    -> App<BassCalcApp> {
+    let app_data = BassCalcApp::new(params, preset_path, load_problems);
     App::new("Bass Calc", (1200, 600), app_data)
 }
 
 impl BassCalcApp {
 
-    pub fn new(params: Parameters) -> BassCalcApp {
+    pub fn new(params: Parameters, preset_path: Option<PathBuf>, load_problems: Vec<String>) -> BassCalcApp {
+        let save_as_text = preset_path.as_ref().map_or(String::new(), |path| path.display().to_string());
+        let preset_dirs = library::preset_dirs();
+        let presets = library::scan(&preset_dirs);
+        let selected_preset = presets.iter().position(|preset| Some(&preset.path) == preset_path.as_ref());
+        let preset_name_text = selected_preset.map_or(String::new(), |i| presets[i].name.clone());
         BassCalcApp {
             ids: None,
             params: params,
             title_ids: vec![],
             param_ids: vec![],
+            solve_inputs: RefCell::new(HashMap::new()),
+            entry_errors: RefCell::new(HashMap::new()),
+            preset_path: RefCell::new(preset_path),
+            save_as_text: RefCell::new(save_as_text),
+            status: RefCell::new(String::new()),
+            preset_dirs,
+            presets: RefCell::new(presets),
+            selected_preset: Cell::new(selected_preset),
+            preset_name_text: RefCell::new(preset_name_text),
+            load_problems: RefCell::new(load_problems),
+            model: Cell::new(Model::PassiveRadiator),
+            inductance: Cell::new(SemiInductance::None),
+            plot: Cell::new(Plot::Magnitude),
+            alignment: Cell::new(Alignment::B4),
+            summary: RefCell::new(None),
+        }
+    }
+
+    /// Work out the summary under the graph again if the parameters or model have changed
+    fn update_summary(&self) {
+        let model = self.model.get();
+        let current = match *self.summary.borrow() {
+            Some(ref summary) => summary.model == model && summary.value_bits == self.params.value_bits(),
+            None => false,
+        };
+        if !current {
+            *self.summary.borrow_mut() = Some(Summary::new(&self.params, model));
+        }
+    }
+
+    /// Set a parameter from the GUI, in SI base units.
+    /// Derived parameters are back-solved through the selected input
+    fn set_param(&self, param: &Param, value: f64) -> Result<(), String> {
+        if param.update_fn.is_none() {
+            self.params.set(param, value);
+            return Ok(());
+        }
+        let inversions = self.params.inversions(param);
+        let selected = self.solve_inputs.borrow().get(&param.name).cloned().unwrap_or(0);
+
+        if let Some(inversion) = inversions.get(selected) {
+            self.params.solve(param, inversion.input, value).map_err(|err| err.to_string())
+        } else {
+            Err(format!("{} is calculated and cannot be set directly", param.name))
+        }
+    }
+
+    /// Set a parameter from text such as `12 mm`, keeping the text as typed
+    fn set_param_display(&self, param: &Param, display: String) -> Result<(), String> {
+        if param.update_fn.is_none() {
+            return self.params.set_display(param, display).map_err(|err| err.to_string());
+        }
+        let value = param.edit_display(display.clone()).map_err(|err| err.to_string())?;
+        self.set_param(param, value)?;
+        // Solving recalculates `param`, which reformats its display
+        let _ = param.edit_display(display);
+        Ok(())
+    }
+
+    fn set_entry_error(&self, param: &Param, result: Result<(), String>) {
+        let mut errors = self.entry_errors.borrow_mut();
+        match result {
+            Ok(()) => errors.remove(&param.name),
+            Err(err) => errors.insert(param.name.clone(), err),
+        };
+    }
+
+    /// Write the parameters to `path`, which becomes the file Save writes to
+    fn save(&self, mut path: PathBuf) {
+        if path.extension().is_none() {
+            path.set_extension("bass");
+        }
+        let status = match save_file(&self.params, &path) {
+            Ok(()) => {
+                let status = format!("Saved {}", path.display());
+                self.refresh_presets(&path);
+                *self.preset_path.borrow_mut() = Some(path);
+                status
+            },
+            Err(err) => format!("Could not save {}: {}", path.display(), err),
+        };
+        *self.status.borrow_mut() = status;
+    }
+
+    /// Load a preset from the library over the built in defaults, so values it leaves out don't
+    /// carry over from the last preset. Save then writes to it
+    fn load_preset(&self, preset: &Preset) {
+        match builtin_defaults() {
+            Ok(defaults) => self.params.reset(&defaults),
+            Err(err) => {
+                *self.status.borrow_mut() = err.to_string();
+                return;
+            },
+        }
+        *self.load_problems.borrow_mut() = load_lenient(&self.params, &preset.path);
+        self.entry_errors.borrow_mut().clear();
+        *self.preset_path.borrow_mut() = Some(preset.path.clone());
+        *self.save_as_text.borrow_mut() = preset.path.display().to_string();
+        *self.status.borrow_mut() = format!("Loaded {}", preset.name);
+    }
+
+    /// Rescan the preset directories, selecting the preset at `select` if it is found
+    fn refresh_presets(&self, select: &Path) {
+        let presets = library::scan(&self.preset_dirs);
+        let selected = presets.iter().position(|preset| preset.path == select);
+        *self.preset_name_text.borrow_mut() = selected.map_or(String::new(), |i| presets[i].name.clone());
+        self.selected_preset.set(selected);
+        *self.presets.borrow_mut() = presets;
+    }
+
+    fn selected_preset(&self) -> Option<Preset> {
+        self.selected_preset.get().and_then(|i| self.presets.borrow().get(i).cloned())
+    }
+
+    /// Report the outcome of a library operation, and show the preset it produced
+    fn preset_changed(&self, result: io::Result<Preset>, action: &str) {
+        match result {
+            Ok(preset) => {
+                *self.status.borrow_mut() = format!("{} {}", action, preset.name);
+                self.refresh_presets(&preset.path);
+            },
+            Err(err) => *self.status.borrow_mut() = err.to_string(),
+        }
+    }
+
+    fn draw_presets(&self, ui: &mut UiCell, w: f64) {
+        let ref ids = self.ids.as_ref().unwrap();
+        let inner_w = w - 20.0;
+        let button_w = (inner_w - 30.0) / 4.0;
+
+        Canvas::new().color(color::BLACK).pad(10.0)
+            .middle_of(ids.tab_presets)
+            .wh_of(ids.tab_presets)
+            .set(ids.preset_panel, ui);
+
+        let name_text = self.preset_name_text.borrow().clone();
+        for edit in TextEdit::new(&name_text)
+            .color(color::WHITE)
+            .w_h(inner_w, 24.0)
+            .mid_top_of(ids.preset_panel)
+            .restrict_to_height(true)
+            .set(ids.preset_name, ui)
+        {
+            *self.preset_name_text.borrow_mut() = edit;
+        }
+
+        for _ in button("Duplicate", button_w)
+            .down_from(ids.preset_name, 8.0)
+            .align_left_of(ids.preset_name)
+            .set(ids.preset_duplicate, ui)
+        {
+            if let Some(preset) = self.selected_preset() {
+                self.preset_changed(library::duplicate(&preset), "Duplicated as");
+            }
+        }
+
+        for _ in button("Rename", button_w)
+            .right_from(ids.preset_duplicate, 10.0)
+            .set(ids.preset_rename, ui)
+        {
+            if let Some(preset) = self.selected_preset() {
+                let name = self.preset_name_text.borrow().clone();
+                let result = library::rename(&preset, &name);
+                if let Ok(ref renamed) = result {
+                    let mut preset_path = self.preset_path.borrow_mut();
+                    if preset_path.as_ref() == Some(&preset.path) {
+                        *preset_path = Some(renamed.path.clone());
+                        *self.save_as_text.borrow_mut() = renamed.path.display().to_string();
+                    }
+                }
+                self.preset_changed(result, "Renamed to");
+            }
+        }
+
+        for _ in button("Delete", button_w)
+            .right_from(ids.preset_rename, 10.0)
+            .set(ids.preset_delete, ui)
+        {
+            if let Some(preset) = self.selected_preset() {
+                *self.status.borrow_mut() = match library::delete(&preset) {
+                    Ok(()) => {
+                        // Save must not quietly bring back the deleted file
+                        let mut preset_path = self.preset_path.borrow_mut();
+                        if preset_path.as_ref() == Some(&preset.path) {
+                            *preset_path = None;
+                            self.save_as_text.borrow_mut().clear();
+                        }
+                        format!("Deleted {}", preset.name)
+                    },
+                    Err(err) => err.to_string(),
+                };
+                self.refresh_presets(&preset.path);
+            }
+        }
+
+        for _ in button("Refresh", button_w)
+            .right_from(ids.preset_delete, 10.0)
+            .set(ids.preset_refresh, ui)
+        {
+            let selected = self.selected_preset().map_or(PathBuf::new(), |preset| preset.path);
+            self.refresh_presets(&selected);
+        }
+
+        let panel_h = ui.kid_area_of(ids.preset_panel).map_or(0.0, |rect| rect.h());
+        let presets = self.presets.borrow().clone();
+        let (mut items, scrollbar) = List::flow_down(presets.len())
+            .item_size(28.0)
+            .scrollbar_on_top()
+            .w_h(inner_w, (panel_h - 66.0).max(0.0))
+            .down_from(ids.preset_duplicate, 10.0)
+            .align_left_of(ids.preset_name)
+            .set(ids.preset_list, ui);
+
+        while let Some(item) = items.next(ui) {
+            let preset = &presets[item.i];
+            let color = if self.selected_preset.get() == Some(item.i) {
+                color::LIGHT_BLUE
+            } else {
+                color::DARK_CHARCOAL
+            };
+            let preset_button = Button::new()
+                .label(&preset.name)
+                .label_font_size(13)
+                .label_color(color::WHITE)
+                .color(color);
+            for _ in item.set(preset_button, ui) {
+                self.selected_preset.set(Some(item.i));
+                *self.preset_name_text.borrow_mut() = preset.name.clone();
+                self.load_preset(preset);
+            }
+        }
+        if let Some(scrollbar) = scrollbar {
+            scrollbar.set(ui);
+        }
+    }
+
+    fn draw_toolbar(&self, ui: &mut UiCell) {
+        let ref ids = self.ids.as_ref().unwrap();
+
+        for _ in Button::new()
+            .label("Save")
+            .label_font_size(12)
+            .w_h(80.0, 26.0)
+            .mid_left_of(ids.toolbar)
+            .set(ids.save_button, ui)
+        {
+            let path = self.preset_path.borrow().clone();
+            match path {
+                Some(path) => self.save(path),
+                None => *self.status.borrow_mut() = "Enter a file name and use Save As".to_string(),
+            }
+        }
+
+        let save_as_text = self.save_as_text.borrow().clone();
+        for edit in TextEdit::new(&save_as_text)
+            .color(color::WHITE)
+            .w_h(200.0, 26.0)
+            .right_from(ids.save_button, 8.0)
+            .restrict_to_height(true)
+            .set(ids.save_as_path, ui)
+        {
+            *self.save_as_text.borrow_mut() = edit;
+        }
+
+        for _ in Button::new()
+            .label("Save As")
+            .label_font_size(12)
+            .w_h(80.0, 26.0)
+            .right_from(ids.save_as_path, 8.0)
+            .set(ids.save_as_button, ui)
+        {
+            let path = self.save_as_text.borrow().trim().to_string();
+            if path.is_empty() {
+                *self.status.borrow_mut() = "Enter a file name to save as".to_string();
+            } else {
+                self.save(PathBuf::from(path));
+            }
+        }
+
+        text(&*self.status.borrow(), 12)
+            .right_from(ids.save_as_button, 8.0)
+            .set(ids.status, ui);
+
+        let model_names = Model::ALL.iter().map(|model| model.name()).collect::<Vec<&str>>();
+        let selected = Model::ALL.iter().position(|model| *model == self.model.get());
+        for index in DropDownList::new(&model_names, selected)
+            .w_h(160.0, 26.0)
+            .mid_right_of(ids.toolbar)
+            .label_font_size(12)
+            .set(ids.model_select, ui)
+        {
+            self.model.set(Model::ALL[index]);
+        }
+
+        let inductance_names = SemiInductance::ALL.iter().map(|inductance| inductance.name()).collect::<Vec<&str>>();
+        let selected = SemiInductance::ALL.iter().position(|inductance| *inductance == self.inductance.get());
+        for index in DropDownList::new(&inductance_names, selected)
+            .w_h(80.0, 26.0)
+            .left_from(ids.model_select, 8.0)
+            .label_font_size(12)
+            .set(ids.inductance_select, ui)
+        {
+            self.inductance.set(SemiInductance::ALL[index]);
+        }
+
+        let plot_names = Plot::ALL.iter().map(|plot| plot.name()).collect::<Vec<&str>>();
+        let selected = Plot::ALL.iter().position(|plot| *plot == self.plot.get());
+        for index in DropDownList::new(&plot_names, selected)
+            .w_h(140.0, 26.0)
+            .left_from(ids.inductance_select, 8.0)
+            .label_font_size(12)
+            .set(ids.plot_select, ui)
+        {
+            self.plot.set(Plot::ALL[index]);
+        }
+
+        // Choosing an alignment previews the box for it, and Apply sets it
+        let alignment_names = Alignment::ALL.iter().map(|alignment| alignment.name()).collect::<Vec<&str>>();
+        let selected = Alignment::ALL.iter().position(|alignment| *alignment == self.alignment.get());
+        for index in DropDownList::new(&alignment_names, selected)
+            .w_h(80.0, 26.0)
+            .left_from(ids.plot_select, 8.0)
+            .label_font_size(12)
+            .set(ids.alignment_select, ui)
+        {
+            self.alignment.set(Alignment::ALL[index]);
+            *self.status.borrow_mut() = match align(&self.params, Alignment::ALL[index]) {
+                Ok(fit) => self.describe_alignment(&fit),
+                Err(err) => err.to_string(),
+            };
+        }
+
+        for _ in Button::new()
+            .label("Apply")
+            .label_font_size(12)
+            .w_h(80.0, 26.0)
+            .left_from(ids.alignment_select, 8.0)
+            .set(ids.alignment_apply, ui)
+        {
+            *self.status.borrow_mut() = match align(&self.params, self.alignment.get()) {
+                Ok(fit) => {
+                    fit.apply(&self.params);
+                    format!("Applied {}", self.describe_alignment(&fit))
+                },
+                Err(err) => err.to_string(),
+            };
+        }
+
+        // Extend the response as far as the ripple and excursion limits allow
+        for _ in Button::new()
+            .label("Optimize")
+            .label_font_size(12)
+            .w_h(80.0, 26.0)
+            .left_from(ids.alignment_apply, 8.0)
+            .set(ids.optimize_button, ui)
+        {
+            let P = &self.params;
+            let model = self.model.get();
+            *self.status.borrow_mut() = match free_params(P, model) {
+                Some(free) => {
+                    let goal = Goal::Extension { ripple: OPTIMIZE_RIPPLE };
+                    let found = optimize(P, model, &free, &goal, OPTIMIZE_ITERATIONS);
+                    found.apply(P);
+                    // The box is shown by its volume rather than its compliance
+                    let values = free.iter()
+                        .map(|param| if param.name == P.Cab.name { &*P.Vb } else { param })
+                        .map(|param| (param, param.v()))
+                        .collect::<Vec<(&Param, f64)>>();
+                    let start = found.history.first().cloned().unwrap_or(found.cost);
+                    let f3 = model_metrics(P, model).f3;
+                    format!("Optimized in {} iterations, cost {:.3} to {:.3}, F3 {}:{}", found.history.len(),
+                        start, found.cost, format_freq(f3), describe_values(&values))
+                },
+                None => format!("Optimize doesn't support the {} box", model.name().to_lowercase()),
+            };
+        }
+    }
+
+    /// Box and passive radiator for an alignment in display units, and how far off it is when the
+    /// driver can't reach it
+    fn describe_alignment(&self, fit: &AlignmentFit) -> String {
+        let P = &self.params;
+        let values = [(&*P.Vb, fit.Vb), (&*P.Fb, fit.Fb), (&*P.Fp, fit.Fp), (&*P.Mmp, fit.Mmp)];
+        let mut description = format!("{}:{}", fit.alignment.name(), describe_values(&values));
+        if fit.error > 1e-3 {
+            description.push_str(&format!(", closest reachable, {:.1} % off", 100.0 * fit.error));
         }
+        description
+    }
+
+    /// Sensitivity, poles, metrics and efficiency of the current parameters, under the graph
+    fn draw_summary(&self, ui: &mut UiCell) {
+        let ref ids = self.ids.as_ref().unwrap();
+        self.update_summary();
+        let summary = self.summary.borrow();
+        let summary = summary.as_ref().unwrap();
+        text(&summary.sensitivity, 12)
+            .top_left_of(ids.graph_column)
+            .set(ids.sensitivity, ui);
+        text(&summary.sections, 12)
+            .down_from(ids.sensitivity, 4.0)
+            .set(ids.sections, ui);
+        text(&summary.metrics, 12)
+            .down_from(ids.sections, 4.0)
+            .set(ids.metrics, ui);
+        text(&summary.efficiency, 12)
+            .down_from(ids.metrics, 4.0)
+            .set(ids.efficiency, ui);
+    }
+
+    /// List problems from loading a preset along the bottom of the graph
+    fn draw_problems(&self, ui: &mut UiCell) {
+        let ref ids = self.ids.as_ref().unwrap();
+        if self.load_problems.borrow().is_empty() {
+            return;
+        }
+        let lines = self.load_problems.borrow().join("\n");
+        let line_count = self.load_problems.borrow().len().min(12);
+
+        Canvas::new()
+            .w_of(ids.graph_column)
+            .h(36.0 + 16.0 * line_count as f64)
+            .mid_bottom_of(ids.graph_column)
+            .pad(8.0)
+            .color(rgb(0.25, 0.1, 0.1))
+            .set(ids.problems, ui);
+
+        for _ in Button::new()
+            .label("Dismiss")
+            .label_font_size(12)
+            .w_h(80.0, 22.0)
+            .top_right_of(ids.problems)
+            .set(ids.problems_dismiss, ui)
+        {
+            self.load_problems.borrow_mut().clear();
+        }
+
+        text(&lines, 12)
+            .color(color::LIGHT_RED)
+            .top_left_of(ids.problems)
+            .left_justify()
+            .line_spacing(4.0)
+            .set(ids.problems_text, ui);
     }
 
     fn draw_list_title(&self, title: &str, ui: &mut UiCell, ids_index: usize, list_id: Id,
@@ -88,6 +650,7 @@ impl BassCalcApp {
         let slider_id = ids[3];
         let entry_id = ids[4];
         let unit_id = ids[5];
+        let solve_id = ids[6];
         
         Canvas::new().align_middle_x_of(list_id).down_from(prev_id, 0.0).w_of(list_id).h(h)
             .color(color::DARK_CHARCOAL).set(canvas_id, ui);
@@ -109,11 +672,18 @@ impl BassCalcApp {
             .set(slider_id, ui)
         {
             
-            param.set_percent(value);
+            let result = if param.update_fn.is_some() {
+                self.set_param(param, param.from_percent(value))
+            } else {
+                self.params.set_percent(param, value);
+                Ok(())
+            };
+            self.set_entry_error(param, result);
             println!("{} value: {}", param.name, value)
         }
 
-        for _edit in TextEdit::new(&format!("{:.*}", param.precision(), param.v()))
+        let display = param.display().clone();
+        for edit in TextEdit::new(&display)
             .color(color::WHITE)
             .w(entry_w)
             .right_from(slider_id, 4.0)
@@ -122,10 +692,32 @@ impl BassCalcApp {
             .set(entry_id, ui)
         {
             println!("Edit {}", edit);
-            param.set_display(edit);
+            let result = self.set_param_display(param, edit);
+            self.set_entry_error(param, result);
+        }
+
+        // Show the unit, or why the last edit was rejected
+        if let Some(err) = self.entry_errors.borrow().get(&param.name) {
+            text(err, 10).color(color::LIGHT_RED).right_from(entry_id, 4.0).set(unit_id, ui);
+        } else {
+            text(&param.unit.symbol, 12).right_from(entry_id, 4.0).set(unit_id, ui);
         }
 
-        text(&param.unit, 12).right_from(entry_id, 4.0).set(unit_id, ui);
+        // Pick which input is adjusted when this derived parameter is edited
+        let inputs = self.params.inversions(param).iter()
+            .map(|inversion| inversion.input)
+            .collect::<Vec<&str>>();
+        if !inputs.is_empty() {
+            let selected = self.solve_inputs.borrow().get(&param.name).cloned().unwrap_or(0);
+            for index in DropDownList::new(&inputs, Some(selected))
+                .w_h(w * 0.12, 20.0)
+                .mid_right_of(canvas_id)
+                .label_font_size(10)
+                .set(solve_id, ui)
+            {
+                self.solve_inputs.borrow_mut().insert(param.name.clone(), index);
+            }
+        }
         canvas_id
     }
 
@@ -148,6 +740,10 @@ impl BassCalcApp {
         let driver = &self.params.driver;
         let passive = &self.params.passive;
         let enclosure = &self.params.enclosure;
+        let vent = &self.params.vent;
+        let bandpass = &self.params.bandpass;
+        let voice_coil = &self.params.voice_coil;
+        let drive = &self.params.drive;
         let constants = &self.params.constant;
 
         let h = 38.0;
@@ -172,21 +768,49 @@ impl BassCalcApp {
         prev_id = self.draw_list_title("Enclosure", ui, 2, list_id, prev_id, w, h);
         prev_id = self.draw_list_params(ui, 2, enclosure, list_id, prev_id, w, h);
 
-        prev_id = self.draw_list_title("Constants", ui, 3, list_id, prev_id, w, h);
-        self.draw_list_params(ui, 3, constants, list_id, prev_id, w, h);
+        prev_id = self.draw_list_title("Vent", ui, 3, list_id, prev_id, w, h);
+        prev_id = self.draw_list_params(ui, 3, vent, list_id, prev_id, w, h);
+
+        prev_id = self.draw_list_title("Bandpass", ui, 4, list_id, prev_id, w, h);
+        prev_id = self.draw_list_params(ui, 4, bandpass, list_id, prev_id, w, h);
+
+        prev_id = self.draw_list_title("Voice coil", ui, 5, list_id, prev_id, w, h);
+        prev_id = self.draw_list_params(ui, 5, voice_coil, list_id, prev_id, w, h);
+
+        prev_id = self.draw_list_title("Drive", ui, 6, list_id, prev_id, w, h);
+        prev_id = self.draw_list_params(ui, 6, drive, list_id, prev_id, w, h);
+
+        prev_id = self.draw_list_title("Constants", ui, 7, list_id, prev_id, w, h);
+        self.draw_list_params(ui, 7, constants, list_id, prev_id, w, h);
     }
 
 }
 
+/// Parameters with values in SI base units, listed in their display units
+fn describe_values(values: &[(&Param, f64)]) -> String {
+    values.iter()
+        .map(|&(param, value)| format!(" {} {:.1} {}", param.name, value / param.unit.scale, param.unit.symbol))
+        .collect()
+}
+
+/// A roll off frequency, or a dash when it is outside the sweep
+fn format_freq(freq: Option<f64>) -> String {
+    freq.map_or("-".to_string(), |freq| format!("{:.1} Hz", freq))
+}
+
 fn text(text: &str, size: u32) -> Text {
     Text::new(text).color(color::WHITE).font_size(size)
 }
 
-fn init_param_ids(id_gen: &mut id::Generator, params: &[Param]) -> Vec<[Id; 6]> {
-    let mut ids: Vec<[Id; 6]> = vec![];
+fn button(label: &str, w: f64) -> Button<button::Flat> {
+    Button::new().label(label).label_font_size(12).w_h(w, 24.0)
+}
+
+fn init_param_ids(id_gen: &mut id::Generator, params: &[Param]) -> Vec<[Id; 7]> {
+    let mut ids: Vec<[Id; 7]> = vec![];
     for _ in params.iter() {
         ids.push([id_gen.next(), id_gen.next(), id_gen.next(),
-                  id_gen.next(), id_gen.next(), id_gen.next()]);
+                  id_gen.next(), id_gen.next(), id_gen.next(), id_gen.next()]);
     }
     ids
 }
@@ -197,6 +821,10 @@ impl AppInterface for BassCalcApp {
         let mut id_gen = ui.widget_id_generator();
         
         self.title_ids = vec![[id_gen.next(), id_gen.next(), id_gen.next()],
+                              [id_gen.next(), id_gen.next(), id_gen.next()],
+                              [id_gen.next(), id_gen.next(), id_gen.next()],
+                              [id_gen.next(), id_gen.next(), id_gen.next()],
+                              [id_gen.next(), id_gen.next(), id_gen.next()],
                               [id_gen.next(), id_gen.next(), id_gen.next()],
                               [id_gen.next(), id_gen.next(), id_gen.next()],
                               [id_gen.next(), id_gen.next(), id_gen.next()]];
@@ -205,6 +833,10 @@ impl AppInterface for BassCalcApp {
         self.param_ids.push(init_param_ids(&mut id_gen, &self.params.driver));
         self.param_ids.push(init_param_ids(&mut id_gen, &self.params.passive));
         self.param_ids.push(init_param_ids(&mut id_gen, &self.params.enclosure));
+        self.param_ids.push(init_param_ids(&mut id_gen, &self.params.vent));
+        self.param_ids.push(init_param_ids(&mut id_gen, &self.params.bandpass));
+        self.param_ids.push(init_param_ids(&mut id_gen, &self.params.voice_coil));
+        self.param_ids.push(init_param_ids(&mut id_gen, &self.params.drive));
         self.param_ids.push(init_param_ids(&mut id_gen, &self.params.constant));
 
         self.ids = Some(Ids::new(id_gen));
@@ -222,6 +854,7 @@ impl AppInterface for BassCalcApp {
 
         // Construct our main `Canvas` tree.
         Canvas::new().flow_down(&[
+            (ids.toolbar, Canvas::new().length(40.0).pad(7.0).color(color::BLACK)),
             (ids.body, Canvas::new()),
         ]).set(ids.root, ui);
 
@@ -235,7 +868,7 @@ impl AppInterface for BassCalcApp {
             .color(color::DARK_CHARCOAL)
             .set(ids.graph_column, ui);
 
-        Tabs::new(&[(ids.tab_driver, "Driver"), (ids.tab_graph, "Graph")])
+        Tabs::new(&[(ids.tab_driver, "Driver"), (ids.tab_presets, "Presets"), (ids.tab_graph, "Graph")])
             .wh_of(ids.param_column)
             .color(color::BLUE)
             .label_color(color::WHITE)
@@ -244,32 +877,48 @@ impl AppInterface for BassCalcApp {
 
         //text("Graph", 36).middle_of(ids.tab_graph).set(ids.tab_graph_label, ui);
 
-        let quarter_lines = widget::grid::Lines::step(0.5_f64).thickness(2.0);
-        let sixteenth_lines = widget::grid::Lines::step(0.125_f64).thickness(1.0);
+        let plot = self.plot.get();
+        let (major_y, minor_y) = plot.y_steps();
+        let (min_y, max_y) = plot.y_range();
+        let (major_x, minor_x) = plot.x_steps();
+        let (min_x, max_x) = plot.x_range();
         let lines = &[
-            quarter_lines.x(),
-            quarter_lines.y(),
-            sixteenth_lines.x(),
-            sixteenth_lines.y(),
+            widget::grid::Lines::step(major_x).thickness(2.0).x(),
+            widget::grid::Lines::step(major_y).thickness(2.0).y(),
+            widget::grid::Lines::step(minor_x).thickness(1.0).x(),
+            widget::grid::Lines::step(minor_y).thickness(1.0).y(),
         ];
 
-        let min_freq = 20.0;
-        let max_freq = 200.0;
         let step = 0.1;
 
-        widget::Grid::new(min_freq, max_freq, -1., 1., lines.iter().cloned())
+        widget::Grid::new(min_x, max_x, min_y, max_y, lines.iter().cloned())
             .color(color::rgb(0.1, 0.12, 0.15))
             .wh_of(ids.graph_column)
             .middle_of(ids.graph_column)
             .set(ids.graph_grid, ui);
-        BassGraph::new(min_freq, max_freq, step, &self.params, Radiator)
-            .color(color::LIGHT_BLUE)
-            .thickness(2.0)
-            .wh_of(ids.graph_column)
-            .middle_of(ids.graph_column)
-            .set(ids.graph, ui);
+        if plot == Plot::PoleZero {
+            PoleZeroPlot::new((min_x, max_x), (min_y, max_y), &self.params, self.model.get())
+                .color(color::LIGHT_BLUE)
+                .wh_of(ids.graph_column)
+                .middle_of(ids.graph_column)
+                .set(ids.pole_zero, ui);
+        } else {
+            BassGraph::new(min_x, max_x, step, &self.params, self.model.get())
+                .plot(plot)
+                .inductance(self.inductance.get())
+                .color(color::LIGHT_BLUE)
+                .thickness(2.0)
+                .wh_of(ids.graph_column)
+                .middle_of(ids.graph_column)
+                .set(ids.graph, ui);
+        }
         }
 
+        self.draw_toolbar(ui);
+        self.draw_problems(ui);
         self.draw_params(ui, param_w);
+        self.draw_presets(ui, param_w);
+        // Last, so the summary takes in this frame's edits
+        self.draw_summary(ui);
     }
 }
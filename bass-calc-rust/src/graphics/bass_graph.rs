@@ -4,24 +4,137 @@
 use conrod::{Color, Colorable, Positionable, Scalar, Sizeable, Widget};
 use conrod::{widget, utils};
 use parameters::Parameters;
-use functions::{BassFnData, bass_fn_point};
+use functions::{Model, SemiInductance, bode, current_drive_db, electrical_impedance, excursion, impulse_response,
+                max_spl, spl, step_response, tone_burst_response, xmax_power};
 
-/// A widget that plots a BassCalc function, which depends on `Parameters`
+/// Which part of the frequency or time response a `BassGraph` plots
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Plot {
+    Magnitude,
+    Spl,
+    Phase,
+    GroupDelay,
+    Excursion,
+    MaxSpl,
+    XmaxPower,
+    Impedance,
+    CurrentDrive,
+    Step,
+    Impulse,
+    ToneBurst,
+    PoleZero,
+}
+
+impl Plot {
+    pub const ALL: [Plot; 13] = [
+        Plot::Magnitude, Plot::Spl, Plot::Phase, Plot::GroupDelay,
+        Plot::Excursion, Plot::MaxSpl, Plot::XmaxPower, Plot::Impedance, Plot::CurrentDrive,
+        Plot::Step, Plot::Impulse, Plot::ToneBurst, Plot::PoleZero,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Plot::Magnitude => "Magnitude",
+            Plot::Spl => "SPL (dB)",
+            Plot::Phase => "Phase (°)",
+            Plot::GroupDelay => "Group delay (ms)",
+            Plot::Excursion => "Excursion (mm)",
+            Plot::MaxSpl => "Max SPL (dB)",
+            Plot::XmaxPower => "Power to Xmax (W)",
+            Plot::Impedance => "Impedance (ohm)",
+            Plot::CurrentDrive => "Current drive change (dB)",
+            Plot::Step => "Step response",
+            Plot::Impulse => "Impulse response (1/ms)",
+            Plot::ToneBurst => "Tone burst response",
+            Plot::PoleZero => "Poles and zeros (Hz)",
+        }
+    }
+
+    /// Whether the X axis is time in ms, rather than frequency in Hz
+    pub fn is_time(&self) -> bool {
+        match *self {
+            Plot::Step | Plot::Impulse | Plot::ToneBurst => true,
+            _ => false,
+        }
+    }
+
+    /// Range of the X axis
+    pub fn x_range(&self) -> (f64, f64) {
+        match *self {
+            Plot::Step | Plot::Impulse => (0., 50.),
+            Plot::ToneBurst => (0., 200.),
+            Plot::PoleZero => (-150., 50.),
+            _ => (20., 200.),
+        }
+    }
+
+    /// Spacing of the major and minor X grid lines
+    pub fn x_steps(&self) -> (f64, f64) {
+        match *self {
+            Plot::Step | Plot::Impulse => (10., 2.5),
+            Plot::ToneBurst => (50., 10.),
+            Plot::PoleZero => (50., 10.),
+            _ => (0.5, 0.125),
+        }
+    }
+
+    /// Range of the Y axis
+    pub fn y_range(&self) -> (f64, f64) {
+        match *self {
+            Plot::Magnitude => (-1., 1.),
+            Plot::Spl => (50., 120.),
+            Plot::Phase => (-180., 360.),
+            Plot::GroupDelay => (-10., 50.),
+            Plot::Excursion => (0., 20.),
+            Plot::MaxSpl => (50., 130.),
+            Plot::XmaxPower => (0., 200.),
+            Plot::Impedance => (0., 50.),
+            Plot::CurrentDrive => (-10., 20.),
+            Plot::Step => (-1., 1.5),
+            Plot::Impulse => (-1., 1.),
+            Plot::ToneBurst => (-2., 2.),
+            Plot::PoleZero => (-100., 100.),
+        }
+    }
+
+    /// Spacing of the major and minor Y grid lines
+    pub fn y_steps(&self) -> (f64, f64) {
+        match *self {
+            Plot::Magnitude => (0.5, 0.125),
+            Plot::Spl => (10., 2.5),
+            Plot::Phase => (90., 22.5),
+            Plot::GroupDelay => (10., 2.5),
+            Plot::Excursion => (5., 1.),
+            Plot::MaxSpl => (10., 2.5),
+            Plot::XmaxPower => (50., 10.),
+            Plot::Impedance => (10., 2.5),
+            Plot::CurrentDrive => (5., 1.),
+            Plot::Step => (0.5, 0.125),
+            Plot::Impulse => (0.5, 0.125),
+            Plot::ToneBurst => (0.5, 0.125),
+            Plot::PoleZero => (50., 10.),
+        }
+    }
+}
+
+/// A widget that plots part of the response of a `Model`, which depends on `Parameters`
 ///
-/// The function returns a list of points, which the widget uses to draw lines according
-/// to the current X and Y scales
+/// The model's functions are evaluated at one frequency or time per pixel, and the widget draws
+/// lines between the points according to the current X and Y scales
 ///
 /// The resulting "path" is drawn using conrod's `PointPath` primitive widget.
 #[derive(WidgetCommon)]
-pub struct BassGraph<'a, F> {
+pub struct BassGraph<'a> {
     #[conrod(common_builder)]
     common: widget::CommonBuilder,
     style: Style,
-    min_freq: f64,
-    max_freq: f64,
+    min_x: f64,
+    max_x: f64,
     step: f64,
+    plot: Plot,
     params: &'a Parameters,
-    f: F,
+    model: Model,
+    inductance: SemiInductance,
 }
 
 /// Unique styling parameters for the `BassGraph` widget.
@@ -47,17 +160,20 @@ pub struct State {
 }
 
 
-impl<'a, F> BassGraph<'a, F> {
-    /// Begin building a new `BassGraph` widget instance.
-    pub fn new(min_freq: f64, max_freq: f64, step: f64, params: &'a Parameters, f: F) -> Self {
+impl<'a> BassGraph<'a> {
+    /// Begin building a new `BassGraph` widget instance. The X range is in Hz, or in ms for time
+    /// plots
+    pub fn new(min_x: f64, max_x: f64, step: f64, params: &'a Parameters, model: Model) -> Self {
         BassGraph {
             common: widget::CommonBuilder::default(),
             style: Style::default(),
-            min_freq: min_freq,
-            max_freq: max_freq,
+            min_x: min_x,
+            max_x: max_x,
             step: step,
+            plot: Plot::Magnitude,
             params: params,
-            f: f,
+            model: model,
+            inductance: SemiInductance::None,
         }
     }
 
@@ -66,12 +182,23 @@ impl<'a, F> BassGraph<'a, F> {
         self.style.thickness = Some(thickness);
         self
     }
+
+    /// The part of the response to plot. Defaults to the magnitude
+    pub fn plot(mut self, plot: Plot) -> Self {
+        self.plot = plot;
+        self
+    }
+
+    /// Model of the voice coil's lossy inductance for the SPL, excursion and impedance plots.
+    /// Defaults to `Le` alone
+    pub fn inductance(mut self, inductance: SemiInductance) -> Self {
+        self.inductance = inductance;
+        self
+    }
 }
 
 
-impl<'a, F> Widget for BassGraph<'a, F>
-          where F: Fn(&Parameters) -> BassFnData,
-{
+impl<'a> Widget for BassGraph<'a> {
     type State = State;
     type Style = Style;
     type Event = ();
@@ -90,23 +217,48 @@ impl<'a, F> Widget for BassGraph<'a, F>
     fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
 
         let widget::UpdateArgs { id, state, style, rect, ui, .. } = args;
-        let BassGraph { min_freq, max_freq, step, f, params, .. } = self;
+        let BassGraph { min_x, max_x, step, plot, params, model, inductance, .. } = self;
 
+        let (min_y, max_y) = plot.y_range();
         let y_to_scalar =
-            |y| utils::map_range(y, -1., 1., rect.bottom(), rect.top());
+            |y| utils::map_range(y, min_y, max_y, rect.bottom(), rect.top());
         let scalar_to_x =
-            |s| utils::map_range(s, rect.left(), rect.right(), min_freq.clone(), max_freq.clone());
-
-        let data = f(&params);
-        let point_iter = (0 .. rect.w() as usize)
-            .map(|x_scalar| {
-                let x_scalar = x_scalar as Scalar + rect.x.start;
-                let x = scalar_to_x(x_scalar);
-                let y = bass_fn_point(&data, x);
-                //println!("{}, {}", x, y);
-                let y_scalar = y_to_scalar(y);
-                [x_scalar, y_scalar]
-            });
+            |s| utils::map_range(s, rect.left(), rect.right(), min_x.clone(), max_x.clone());
+
+        // One point per pixel. The whole sweep is evaluated at once so the phase can be unwrapped,
+        // and the time responses integrated from the start
+        let x_scalars = (0 .. rect.w() as usize)
+            .map(|x_scalar| x_scalar as Scalar + rect.x.start)
+            .collect::<Vec<Scalar>>();
+        let xs = x_scalars.iter().map(|&x_scalar| scalar_to_x(x_scalar)).collect::<Vec<f64>>();
+        let freqs = &xs;
+        let times = xs.iter().map(|ms| ms / 1000.0).collect::<Vec<f64>>();
+        // Magnitude, phase, group delay and the time responses are of the model's transfer function
+        // alone. Only the plots that take `inductance` include the voice coil inductance
+        let data = (model.response())(params);
+        let response = || bode(&data, freqs);
+        let ys = match plot {
+            Plot::Magnitude => response().magnitude(),
+            Plot::Spl => spl(params, model, inductance, freqs),
+            Plot::Phase => response().phase,
+            Plot::GroupDelay => response().group_delay,
+            Plot::Excursion => excursion(params, model, inductance, params.Pin.v(), freqs),
+            Plot::MaxSpl => max_spl(params, model, inductance, freqs),
+            Plot::XmaxPower => xmax_power(params, model, inductance, freqs),
+            Plot::Impedance => electrical_impedance(params, model, inductance, freqs).iter().map(|z| z.norm()).collect(),
+            Plot::CurrentDrive => current_drive_db(params, model, inductance, freqs),
+            // Nothing is drawn for a transfer function the time responses can't realise
+            Plot::Step => step_response(&data, &times).unwrap_or_default(),
+            Plot::Impulse => impulse_response(&data, &times)
+                .map(|impulse| impulse.iter().map(|h| h / 1000.0).collect::<Vec<f64>>())
+                .unwrap_or_default(),
+            Plot::ToneBurst => tone_burst_response(&data, params.Ftb.v(), params.Ntb.v(), &times)
+                .unwrap_or_default(),
+            // Drawn by `PoleZeroPlot`
+            Plot::PoleZero => vec![],
+        };
+        let point_iter = x_scalars.into_iter().zip(ys.into_iter())
+            .map(|(x_scalar, y)| [x_scalar, y_to_scalar(y)]);
 
         let thickness = style.thickness(ui.theme());
         let color = style.color(ui.theme());
@@ -122,6 +274,6 @@ impl<'a, F> Widget for BassGraph<'a, F>
 
 }
 
-impl<'a, F> Colorable for BassGraph<'a, F> {
+impl<'a> Colorable for BassGraph<'a> {
     builder_method!(color { style.color = Some(Color) });
 }
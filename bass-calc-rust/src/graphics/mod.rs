@@ -11,7 +11,10 @@ use conrod::glium::glutin::{ContextBuilder, WindowBuilder};
 use std;
 
 pub mod bass_graph;
-pub use self::bass_graph::BassGraph;
+pub use self::bass_graph::{BassGraph, Plot};
+
+pub mod pole_zero_plot;
+pub use self::pole_zero_plot::PoleZeroPlot;
 
 pub mod app;
 
@@ -0,0 +1,144 @@
+//! A widget for plotting the poles and zeros of a model's response in the s plane
+
+use std::f64::consts::PI;
+use conrod::{Color, Colorable, Positionable, Scalar, Widget};
+use conrod::{widget, utils};
+use parameters::Parameters;
+use functions::{Model, pole_zero};
+
+/// A widget that marks the poles of a `Model`'s response with crosses and its zeros with circles
+///
+/// Both axes are in Hz, the real part of each root divided by 2π on X and the imaginary part on Y.
+/// Poles in the right half plane are drawn in `unstable_color`
+#[derive(WidgetCommon)]
+pub struct PoleZeroPlot<'a> {
+    #[conrod(common_builder)]
+    common: widget::CommonBuilder,
+    style: Style,
+    x_range: (f64, f64),
+    y_range: (f64, f64),
+    params: &'a Parameters,
+    model: Model,
+}
+
+/// Unique styling parameters for the `PoleZeroPlot` widget.
+#[derive(Copy, Clone, Debug, Default, PartialEq, WidgetStyle)]
+pub struct Style {
+    /// Half the width of each marker.
+    #[conrod(default = "5.0")]
+    pub size: Option<Scalar>,
+    /// The thickness of the marker lines.
+    #[conrod(default = "2.0")]
+    pub thickness: Option<Scalar>,
+    /// The color of the markers.
+    #[conrod(default = "theme.shape_color")]
+    pub color: Option<Color>,
+    /// The color of poles in the right half plane.
+    #[conrod(default = "::conrod::color::RED")]
+    pub unstable_color: Option<Color>,
+}
+
+widget_ids! {
+    struct Ids {
+        pole_lines[],
+        zero_circles[],
+    }
+}
+
+/// Unique state stored between updates for the `PoleZeroPlot` widget.
+pub struct State {
+    ids: Ids,
+}
+
+impl<'a> PoleZeroPlot<'a> {
+    /// Begin building a new `PoleZeroPlot` widget instance. Ranges are in Hz
+    pub fn new(x_range: (f64, f64), y_range: (f64, f64), params: &'a Parameters, model: Model) -> Self {
+        PoleZeroPlot {
+            common: widget::CommonBuilder::default(),
+            style: Style::default(),
+            x_range: x_range,
+            y_range: y_range,
+            params: params,
+            model: model,
+        }
+    }
+}
+
+impl<'a> Widget for PoleZeroPlot<'a> {
+    type State = State;
+    type Style = Style;
+    type Event = ();
+
+    fn init_state(&self, id_gen: widget::id::Generator) -> Self::State {
+        State {
+            ids: Ids::new(id_gen),
+        }
+    }
+
+    fn style(&self) -> Self::Style {
+        self.style.clone()
+    }
+
+    /// Update the state of the PoleZeroPlot.
+    fn update(self, args: widget::UpdateArgs<Self>) -> Self::Event {
+
+        let widget::UpdateArgs { id, state, style, rect, ui, .. } = args;
+        let PoleZeroPlot { x_range, y_range, params, model, .. } = self;
+
+        let to_scalar = |w: f64, (min, max): (f64, f64), start: Scalar, end: Scalar| {
+            utils::map_range(w / (2.0 * PI), min, max, start, end)
+        };
+        let to_point = |re: f64, im: f64| {
+            [to_scalar(re, x_range, rect.left(), rect.right()),
+             to_scalar(im, y_range, rect.bottom(), rect.top())]
+        };
+
+        // Roots of the model's transfer function, which leaves out the voice coil inductance
+        let roots = pole_zero(&(model.response())(params));
+        let unstable = roots.unstable_poles();
+
+        // Two lines per pole, and one circle per zero
+        if state.ids.pole_lines.len() != 2 * roots.poles.len() {
+            state.update(|state| {
+                state.ids.pole_lines.resize(2 * roots.poles.len(), &mut ui.widget_id_generator())
+            });
+        }
+        if state.ids.zero_circles.len() != roots.zeros.len() {
+            state.update(|state| {
+                state.ids.zero_circles.resize(roots.zeros.len(), &mut ui.widget_id_generator())
+            });
+        }
+
+        let size = style.size(ui.theme());
+        let thickness = style.thickness(ui.theme());
+        let color = style.color(ui.theme());
+        for (i, pole) in roots.poles.iter().enumerate() {
+            let [x, y] = to_point(pole.re, pole.im);
+            let pole_color = if unstable.contains(pole) { style.unstable_color(ui.theme()) } else { color };
+            let strokes = [([x - size, y - size], [x + size, y + size]),
+                           ([x - size, y + size], [x + size, y - size])];
+            for (j, &(start, end)) in strokes.iter().enumerate() {
+                widget::Line::abs(start, end)
+                    .color(pole_color)
+                    .thickness(thickness)
+                    .parent(id)
+                    .graphics_for(id)
+                    .set(state.ids.pole_lines[2 * i + j], ui);
+            }
+        }
+        for (i, zero) in roots.zeros.iter().enumerate() {
+            let [x, y] = to_point(zero.re, zero.im);
+            widget::Circle::outline_styled(size, widget::line::Style::solid().thickness(thickness))
+                .x_y(x, y)
+                .color(color)
+                .parent(id)
+                .graphics_for(id)
+                .set(state.ids.zero_circles[i], ui);
+        }
+    }
+
+}
+
+impl<'a> Colorable for PoleZeroPlot<'a> {
+    builder_method!(color { style.color = Some(Color) });
+}
@@ -17,8 +17,14 @@ mod graphics;
 use graphics::*;
 
 fn main() {
-    let P = parameters::file_defaults();
+    let (P, load_problems) = match parameters::file_defaults() {
+        Ok(defaults) => defaults,
+        Err(err) => {
+            println!("{}", err);
+            return;
+        }
+    };
     
-    let mut app = app::make_app(P);
+    let mut app = app::make_app(P, parameters::defaults_path(), load_problems);
     app.run();
 }
\ No newline at end of file
@@ -1,145 +1,604 @@
 
 use parameters::params::*;
+use parameters::graph::GraphError;
+use parameters::parse::{angle_scale, parse_quantity, parse_unit, UnitError};
+use parameters::units::*;
 use find_folder;
-use std::path::PathBuf;
-use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::io;
 use csv::StringRecord;
 
 const FILE_RECORD_LEN: usize = 6;
 
-fn update_parameter(params: &Parameters, record: StringRecord) {
-    if record.len() != FILE_RECORD_LEN {
-        println!("Invalid file record len on line {}", record.position().unwrap().line());
-    } else {
-        let name = record.get(0).unwrap().to_string();
-        if let Some(param) = params.get(&name) {
-            
-                let val_str = record.get(1).unwrap().to_string();
-                if let Ok(val) = val_str.trim().parse::<f64>() {
-                    param.set(val);
-                } else {
-                    println!("Could not parse default value {} for {}", val_str, name);
-                }
-                
-                let prec_str = record.get(4).unwrap().to_string();
-                if let Ok(prec) = prec_str.trim().parse::<usize>() {
-                    param.set_precision(prec);
-                } else {
-                    println!("Could not parse precision {} for {}", val_str, name);
+/// The Python prototype's `defaults.bass` has no Prec. column, and leaves the Units column out for
+/// dimensionless parameters
+const PROTOTYPE_RECORD_LEN: usize = 5;
+const PROTOTYPE_UNITLESS_RECORD_LEN: usize = 4;
+
+/// Unit named in a record's Units column, checked against the unit `param` is displayed in. An
+/// empty Units column means the display unit
+fn record_unit(param: &Param, unit_str: &str) -> Result<DisplayUnit, UnitError> {
+    let unit_str = unit_str.trim();
+    if unit_str.is_empty() {
+        return Ok(param.unit.clone());
+    }
+    let parsed = parse_unit(unit_str)?;
+    if parsed.dimension != param.unit.dimension {
+        return Err(UnitError::Incompatible {
+            unit: unit_str.to_string(),
+            expected: param.unit.symbol.clone(),
+        });
+    }
+    let scale = parsed.scale * angle_scale(&parsed, &parse_unit(&param.unit.symbol)?);
+    Ok(DisplayUnit { symbol: unit_str.to_string(), scale, dimension: parsed.dimension })
+}
+
+/// Field of `record` in the column `COLUMN_NAMES[column]`, or `None` if records of its length
+/// don't have that column
+fn record_field(record: &StringRecord, column: usize) -> Option<&str> {
+    match (record.len(), column) {
+        (FILE_RECORD_LEN, _) => record.get(column),
+        (_, 4) => None,
+        (PROTOTYPE_RECORD_LEN, 5) => record.get(4),
+        (_, 5) => None,
+        _ => record.get(column),
+    }
+}
+
+const COLUMN_NAMES: [&str; FILE_RECORD_LEN] = ["Name", "Default", "Min", "Max", "Prec.", "Units"];
+
+/// Problem with one line of a preset file
+#[derive(Debug, PartialEq)]
+pub struct LoadProblem {
+    pub line: u64,
+    /// Index of the field the problem is in, or `None` if it is with the whole line
+    pub column: Option<usize>,
+    pub param: Option<String>,
+    pub reason: String,
+}
+
+impl fmt::Display for LoadProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}", self.line)?;
+        if let Some(column) = self.column {
+            write!(f, ", {} column", COLUMN_NAMES[column])?;
+        }
+        if let Some(ref param) = self.param {
+            write!(f, " ({})", param)?;
+        }
+        write!(f, ": {}", self.reason)
+    }
+}
+
+#[derive(Debug)]
+pub enum LoadError {
+    /// The file could not be opened or read
+    Io { path: PathBuf, error: io::Error },
+    /// Problems found in strict mode. Nothing was loaded
+    Invalid { path: PathBuf, problems: Vec<LoadProblem> },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LoadError::Io { ref path, ref error } => write!(f, "Could not read {}: {}", path.display(), error),
+            LoadError::Invalid { ref path, ref problems } => {
+                write!(f, "Could not load {}:", path.display())?;
+                for problem in problems.iter() {
+                    write!(f, "\n  {}", problem)?;
                 }
+                Ok(())
+            },
+        }
+    }
+}
 
-        } else {
-            println!("Unknown parameter {}", &name);
+/// How `load_file` treats problems in a preset file
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LoadMode {
+    /// Load nothing if there are any problems
+    Strict,
+    /// Load everything that can be read, and return the problems as warnings
+    Lenient,
+}
+
+/// Values read from one record, in SI base units
+struct Record {
+    param: Param,
+    value: Option<f64>,
+    range: Option<(f64, f64)>,
+    precision: Option<usize>,
+}
+
+fn read_record(params: &Parameters, record: &StringRecord, problems: &mut Vec<LoadProblem>) -> Option<Record> {
+    let line = record.position().map_or(0, |pos| pos.line());
+    let name = record.get(0).unwrap_or("").trim().to_string();
+
+    let mut problem = |column: Option<usize>, param: Option<&str>, reason: String| {
+        problems.push(LoadProblem { line, column, param: param.map(|p| p.to_string()), reason });
+    };
+
+    let lengths = [FILE_RECORD_LEN, PROTOTYPE_RECORD_LEN, PROTOTYPE_UNITLESS_RECORD_LEN];
+    if !lengths.contains(&record.len()) {
+        problem(None, Some(&name), format!("expected {} fields, found {}", FILE_RECORD_LEN, record.len()));
+        return None;
+    }
+    let param = match params.get(&name) {
+        Some(param) => param,
+        None => {
+            problem(Some(0), Some(&name), "unknown parameter".to_string());
+            return None;
+        },
+    };
+
+    let unit = match record_unit(&param, record_field(record, 5).unwrap_or("")) {
+        Ok(unit) => unit,
+        Err(err) => {
+            problem(Some(5), Some(&name), err.to_string());
+            return None;
+        },
+    };
+
+    // Values in the file are in its unit, which may differ from the display unit
+    let min = parse_quantity(&record[2], &unit).map_err(|err| problem(Some(2), Some(&name), err.to_string()));
+    let max = parse_quantity(&record[3], &unit).map_err(|err| problem(Some(3), Some(&name), err.to_string()));
+    let range = match (min, max) {
+        (Ok(min), Ok(max)) if min > max => {
+            problem(Some(2), Some(&name), format!("min {} is above max {}", record[2].trim(), record[3].trim()));
+            None
+        },
+        (Ok(min), Ok(max)) => Some((min, max)),
+        _ => None,
+    };
+
+    let (min, max) = range.unwrap_or((param.min(), param.max()));
+    let value = match parse_quantity(&record[1], &unit) {
+        Ok(value) if value < min || value > max => {
+            problem(Some(1), Some(&name), format!("{} is outside the range {} to {} {}", record[1].trim(),
+                min / unit.scale, max / unit.scale, unit.symbol));
+            Some(value.max(min).min(max))
+        },
+        Ok(value) => Some(value),
+        Err(err) => {
+            problem(Some(1), Some(&name), err.to_string());
+            None
+        },
+    };
+
+    // Without a Prec. column the parameter keeps its precision
+    let precision = record_field(record, 4).and_then(|field| match field.trim().parse::<usize>() {
+        Ok(precision) => Some(precision),
+        Err(_) => {
+            problem(Some(4), Some(&name), format!("'{}' is not a whole number", field.trim()));
+            None
+        },
+    });
+
+    Some(Record { param, value, range, precision })
+}
+
+/// Records of a preset in the table format of `defaults.bass`
+fn read_table(params: &Parameters, text: &str) -> (Vec<Record>, Vec<LoadProblem>) {
+    use csv::ReaderBuilder;
+
+    let mut reader = ReaderBuilder::new().comment(Some(b'#'))
+                                         .flexible(true)
+                                         .from_reader(text.as_bytes());
+    let mut records = vec![];
+    let mut problems = vec![];
+    for result in reader.records() {
+        match result {
+            Ok(record) => records.extend(read_record(params, &record, &mut problems)),
+            Err(err) => problems.push(LoadProblem {
+                line: err.position().map_or(0, |pos| pos.line()),
+                column: None,
+                param: None,
+                reason: err.to_string(),
+            }),
         }
     }
+    (records, problems)
 }
 
-pub fn load_file(params: Parameters, path: &PathBuf) -> Parameters {
-    use csv::{ReaderBuilder, ErrorKind};
+/// Whether a preset was written by the Python prototype, as `name=value` lines
+fn is_key_value(text: &str) -> bool {
+    text.lines().map(|line| line.trim())
+        .find(|line| !line.is_empty() && !line.starts_with('#'))
+        .map_or(false, |line| line.contains('=') && !line.contains(','))
+}
 
-    if let Ok(mut reader) = ReaderBuilder::new().comment(Some(b'#'))
-                                    .from_path(path) {
+/// Quantity text written by pint, either `9.25 millimeter` or `<Quantity(9.25, 'millimeter')>`
+fn pint_quantity(text: &str) -> String {
+    let text = text.trim();
+    if text.starts_with("<Quantity(") && text.ends_with(")>") {
+        let inner = &text["<Quantity(".len()..text.len() - 2];
+        let mut parts = inner.splitn(2, ',');
+        let value = parts.next().unwrap_or("").trim();
+        let unit = parts.next().unwrap_or("").trim().trim_matches('\'');
+        return format!("{} {}", value, unit);
+    }
+    text.to_string()
+}
 
-        for result in reader.records() {
-            match result {
-                Ok(record) => update_parameter(&params, record),
-                Err(err) => {
-                    match err.kind() {
-                        ErrorKind::UnequalLengths {pos: Some(p), .. } => {
-                            println!("Error! Missing data on line {}", p.line())
-                        },
-                        _ => println!("Unknown error parsing parameter file")
-                    };
-                }
+/// Records of a preset saved by the Python prototype's `file_utils.save_file`. Only values are
+/// stored, so ranges and precisions are left as they are
+fn read_key_values(params: &Parameters, text: &str) -> (Vec<Record>, Vec<LoadProblem>) {
+    let mut records = vec![];
+    let mut problems = vec![];
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut problem = |param: Option<&str>, reason: String| {
+            problems.push(LoadProblem { line: index as u64 + 1, column: None,
+                                        param: param.map(|p| p.to_string()), reason });
+        };
+
+        let (name, value_str) = match line.find('=') {
+            Some(split) => (line[..split].trim(), &line[split + 1..]),
+            None => {
+                problem(None, "expected name=value".to_string());
+                continue;
+            },
+        };
+        let param = match params.get(name) {
+            Some(param) => param,
+            None => {
+                problem(Some(name), "unknown parameter".to_string());
+                continue;
+            },
+        };
+        let value = match parse_quantity(&pint_quantity(value_str), &param.unit) {
+            Ok(value) => value,
+            Err(err) => {
+                problem(Some(name), err.to_string());
+                continue;
+            },
+        };
+        if !param.in_range(value) {
+            problem(Some(name), format!("{} is outside the range {} to {} {}", value_str.trim(),
+                param.min() / param.unit.scale, param.max() / param.unit.scale, param.unit.symbol));
+        }
+        let value = value.max(param.min()).min(param.max());
+        records.push(Record { param, value: Some(value), range: None, precision: None });
+    }
+    (records, problems)
+}
+
+/// Load a preset file over `params`, returning any problems with it as warnings.
+/// In strict mode nothing is changed unless the whole file is valid.
+///
+/// Files saved by the Python prototype as `name=value` lines are also read
+pub fn load_file(params: &Parameters, path: &Path, mode: LoadMode) -> Result<Vec<LoadProblem>, LoadError> {
+    let text = fs::read_to_string(path).map_err(|error| LoadError::Io { path: path.to_path_buf(), error })?;
+
+    let (records, problems) = if is_key_value(&text) {
+        read_key_values(params, &text)
+    } else {
+        read_table(params, &text)
+    };
+
+    if mode == LoadMode::Strict && !problems.is_empty() {
+        return Err(LoadError::Invalid { path: path.to_path_buf(), problems });
+    }
+
+    for record in records {
+        if let Some((min, max)) = record.range {
+            record.param.set_range(min, max);
+        }
+        if let Some(value) = record.value {
+            record.param.set(value);
+        }
+        if let Some(precision) = record.precision {
+            record.param.set_precision(precision);
+        }
+    }
+    params.update_all();
+    Ok(problems)
+}
+
+/// Format with at most 12 significant digits, so unit conversion leaves no noise like `0.30000000000000004`
+fn format_number(value: f64) -> String {
+    format!("{}", format!("{:.11e}", value).parse::<f64>().unwrap())
+}
+
+/// Fields of the record for `param`, in its display unit. The value is written in full rather
+/// than to its display precision, so a fitted or back-solved design loads back unchanged
+fn record_fields(param: &Param) -> Vec<String> {
+    let scale = param.unit.scale;
+    vec![param.name.clone(),
+         format_number(param.display_value()),
+         format_number(param.min() / scale),
+         format_number(param.max() / scale),
+         param.precision().to_string(),
+         param.unit.symbol.clone()]
+}
+
+/// Lay out rows of fields in aligned columns, like `defaults.bass`
+fn format_rows(rows: &[Vec<String>]) -> Vec<String> {
+    let mut widths = [0; FILE_RECORD_LEN];
+    for row in rows.iter() {
+        for (width, field) in widths.iter_mut().zip(row.iter()) {
+            *width = (*width).max(field.chars().count());
+        }
+    }
+    rows.iter().map(|row| {
+        let mut line = String::new();
+        for (i, field) in row.iter().enumerate() {
+            if i + 1 < row.len() {
+                line.push_str(&format!("{:<1$}", format!("{},", field), widths[i] + 2));
+            } else {
+                line.push_str(field);
             }
         }
+        line.trim_right().to_string()
+    }).collect()
+}
+
+/// Replace the fields of a record line, keeping the name as written and starting each field
+/// in the same column as before so the columns stay aligned
+fn rewrite_record(line: &str, fields: &[String]) -> String {
+    let mut starts = vec![];
+    let mut col = 0;
+    for segment in line.split(',') {
+        starts.push(col + segment.chars().take_while(|c| c.is_whitespace()).count());
+        col += segment.chars().count() + 1;
+    }
+
+    let mut new = line.split(',').next().unwrap().to_string();
+    let mut col = new.chars().count();
+    for (i, field) in fields.iter().enumerate().skip(1) {
+        new.push(',');
+        col += 1;
+        if field.is_empty() {
+            continue;
+        }
+        let start = starts.get(i).cloned().unwrap_or(0).max(col + 1);
+        new.push_str(&" ".repeat(start - col));
+        new.push_str(field);
+        col = start + field.chars().count();
+    }
+    new
+}
+
+/// Name of the parameter a line of a preset file sets, if it isn't a comment or blank
+fn record_name(line: &str) -> Option<&str> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        None
+    } else {
+        line.split(',').next().map(|name| name.trim())
     }
-    params
 }
 
-pub fn file_defaults() -> Parameters {
+/// Write the current parameters to a preset file in the format `load_file` reads.
+/// If the file exists, its comments, line order and column alignment are kept
+pub fn save_file(params: &Parameters, path: &Path) -> io::Result<()> {
+    let all = params.driver.iter().chain(params.passive.iter())
+        .chain(params.enclosure.iter()).chain(params.vent.iter())
+        .chain(params.bandpass.iter()).chain(params.voice_coil.iter()).chain(params.drive.iter())
+        .chain(params.constant.iter());
+
+    let mut lines = vec![];
+    let mut written = HashSet::new();
+    let final_newline;
+
+    if path.exists() {
+        let existing = fs::read_to_string(path)?;
+        final_newline = existing.ends_with('\n');
+        for line in existing.lines() {
+            match record_name(line).and_then(|name| params.get(name)) {
+                Some(param) => {
+                    lines.push(rewrite_record(line, &record_fields(&param)));
+                    written.insert(param.name.clone());
+                },
+                None => lines.push(line.to_string()),
+            }
+        }
+        let rows = all.filter(|param| !written.contains(&param.name))
+            .map(record_fields)
+            .collect::<Vec<_>>();
+        lines.extend(format_rows(&rows));
+    } else {
+        let mut rows = vec![COLUMN_NAMES.iter().map(|s| s.to_string()).collect()];
+        rows.extend(all.map(record_fields));
+        let mut formatted = format_rows(&rows).into_iter();
+
+        lines.push(formatted.next().unwrap());
+        let groups = [("Driver", params.driver.len()), ("Passive radiator", params.passive.len()),
+                      ("Enclosure", params.enclosure.len()), ("Vent", params.vent.len()),
+                      ("Bandpass", params.bandpass.len()), ("Voice coil", params.voice_coil.len()),
+                      ("Drive", params.drive.len()),
+                      ("Constants", params.constant.len())];
+        for &(title, len) in groups.iter() {
+            lines.push(String::new());
+            lines.push(format!("# {} parameters", title));
+            lines.extend(formatted.by_ref().take(len));
+        }
+        final_newline = true;
+    }
 
-    let mut P = builtin_defaults();
-    
-    if let Ok(resources) = find_folder::Search::KidsThenParents(3, 5).for_folder("resources") {
+    let mut text = lines.join("\n");
+    if final_newline {
+        text.push('\n');
+    }
+    fs::write(path, text)
+}
 
-        let defaults_path = resources.join("presets/defaults.bass");
+/// Location of the preset loaded at startup
+pub fn defaults_path() -> Option<PathBuf> {
+    find_folder::Search::KidsThenParents(3, 5).for_folder("resources").ok()
+        .map(|resources| resources.join("presets/defaults.bass"))
+}
 
-        P = load_file(P, &defaults_path);
+/// Load a preset in lenient mode, describing each problem with it for display
+pub fn load_lenient(params: &Parameters, path: &Path) -> Vec<String> {
+    match load_file(params, path, LoadMode::Lenient) {
+        Ok(problems) => problems.iter()
+            .map(|problem| format!("{}: {}", path.display(), problem))
+            .collect(),
+        Err(err) => vec![err.to_string()],
     }
+}
+
+/// Built in defaults overridden by the preset loaded at startup, with any problems loading it
+pub fn file_defaults() -> Result<(Parameters, Vec<String>), GraphError> {
 
-    P
+    let P = builtin_defaults()?;
+    
+    let messages = match defaults_path() {
+        Some(defaults_path) => load_lenient(&P, &defaults_path),
+        None => vec!["Could not find the resources folder".to_string()],
+    };
+
+    Ok((P, messages))
 }
 
-pub fn builtin_defaults() -> Parameters {
+pub fn builtin_defaults() -> Result<Parameters, GraphError> {
 
-        // Environmental parameters
-    let ρ0 = param_simple("ρ0", "kg / m^3", 1.1839, 1.0, 1.4, 4);
-    let c = param_simple("c", "m/s", 345.0, 340.0, 350.0, 1);
-    let t = param_simple("t", "s", 1.0, 0.9, 1.1, 1);
+    // Environmental parameters
+    let ρ0 = param_simple("ρ0", kg_per_m3(), 1.1839, 1.0, 1.4, 4);
+    let c = param_simple("c", m_per_s(), 345.0, 340.0, 350.0, 1);
+    let t = param_simple("t", s(), 1.0, 0.9, 1.1, 1);
 
     // Driver low level parameters
-    let Xmax = param_simple("Xmax", "mm", 3.0, 0.0, 100.0, 1);
-    let Vd = param("Vd", "Liter", 0.1, 0.1, 100.0, 1, vd_update);
-    let Sd = param_simple("Sd", "cm ^ 2", 10.0, 1.0, 1000.0, 1);
-    let Bl = param_simple("Bl", "tesla m", 1.0, 0.1, 20.0, 1);
-    let Re = param_simple("Re", "ohm", 4.0, 0.1, 1000.0, 1);
-    let Mmd = param_simple("Mmd", "g", 10.0, 1.0, 1000.0, 1);
-    let Mms = param("Mms", "g", 10.0, 1.0, 1000.0, 1, mms_update);
-    let Mas = param("Mas", "g / cm^4", 10.0, 1.0, 1000.0, 1, mas_update);
-    let Rms = param_simple("Rms", "N * s / m", 4.0, 0.0, 1000.0, 1);
-    let Ras = param("Ras", "(Pa * s) / m^3", 1.0, 0.0, 1000.0, 1, ras_update);
-    let Cms = param_simple("Cms", "m / N", 1.0, 0.1, 1000.0, 1);
-    let Cas = param("Cas", "m^5 / N", 1.0, 0.0, 100.0, 1, cas_update);
-    let Vas = param("Vas", "liter", 1.0, 0.0, 100.0, 1, vas_update);
-
-    let Rg = param_simple("Rg", "", 0.0, 0.0, 1000.0, 1);
+    let Xmax = param_simple("Xmax", mm(), 3.0, 0.0, 100.0, 1);
+    let Vd = param("Vd", liter(), 0.1, 0.0, 10.0, 4, vd_update);
+    let Sd = param_simple("Sd", cm2(), 10.0, 1.0, 1000.0, 1);
+    let Bl = param_simple("Bl", tesla_m(), 1.0, 0.1, 50.0, 2);
+    let Re = param_simple("Re", ohm(), 4.0, 0.1, 100.0, 1);
+    let Mmd = param_simple("Mmd", g(), 10.0, 0.1, 1000.0, 1);
+    let Mms = param("Mms", g(), 10.0, 0.1, 1000.0, 2, mms_update);
+    let Mas = param("Mas", kg_per_m4(), 10.0, 1.0, 1000000.0, 1, mas_update);
+    let Rms = param_simple("Rms", n_s_per_m(), 4.0, 0.0, 100.0, 2);
+    let Ras = param("Ras", pa_s_per_m3(), 1.0, 0.0, 100000000.0, 0, ras_update);
+    let Cms = param_simple("Cms", mm_per_n(), 1.0, 0.01, 10.0, 3);
+    let Cas = param("Cas", cm5_per_n(), 1.0, 0.0, 100000.0, 1, cas_update);
+    let Vas = param("Vas", liter(), 1.0, 0.0, 1000.0, 2, vas_update);
+
+    let Rg = param_simple("Rg", ohm(), 0.0, 0.0, 100.0, 2);
+    let Pe = param_simple("Pe", watt(), 50.0, 0.0, 10000.0, 1);
 
     // Driver mid level parameters
-    let Ts = param("Ts", "s", 0.02, 0.0002, 0.2, 4, ts_update);
-    let ωs = param("ωs", "Hz", 50.0, 5.0, 5000.0, 1, ωs_update);
-    let Fs = param("Fs", "Hz", 314.1, 31.4, 31415.9, 1, fs_update);
-    let Qes = param("Qes", "", 0.5, 0.0, 30.0, 1, qes_update);
-    let Qms = param("Qms", "", 0.5, 0.0, 30.0, 1, qms_update);
-    let Qts = param("Qts", "", 0.5, 0.0, 30.0, 1, qts_update);
-    let Qs = param("Qs", "", 0.5, 0.0, 30.0, 1, qs_update);
-    let Cab = param_simple("Cab", "m^5 / N", 1.0, 0.0, 100.0, 1);
-    let Vb = param("Vb", "liter", 0.1, 0.0, 100.0, 1, vb_update);
+    let Ts = param("Ts", s(), 0.02, 0.0, 0.2, 4, ts_update);
+    let ωs = param("ωs", rad_per_s(), 50.0, 1.0, 100000.0, 1, ωs_update);
+    let Fs = param("Fs", hz(), 314.1, 1.0, 20000.0, 1, fs_update);
+    let Qes = param("Qes", unitless(), 0.5, 0.0, 30.0, 2, qes_update);
+    let Qe = param("Qe", unitless(), 0.5, 0.0, 1000.0, 2, qe_update);
+    let Qms = param("Qms", unitless(), 0.5, 0.0, 100.0, 2, qms_update);
+    let Qts = param("Qts", unitless(), 0.5, 0.0, 30.0, 2, qts_update);
+    let Qs = param("Qs", unitless(), 0.5, 0.0, 30.0, 2, qs_update);
+    let Cab = param_simple("Cab", cm5_per_n(), 710.0, 1.0, 100000.0, 1);
+    let Vb = param("Vb", liter(), 10.0, 0.0, 1000.0, 2, vb_update);
 
     // Passive radiator low level parameters
-    let Vap = param("Vap", "liter", 1.0, 0.0, 100.0, 1, vap_update);
-    let Cmp = param_simple("Cmp", "m / N", 1.0, 0.0, 1000.0, 1);
-    let Cap = param("Cap", "m^5 / N", 1.0, 0.0, 100.0, 1, cap_update);
-    let Rmp = param_simple("Rmp", "N * s / m", 4.0, 0.0, 1000.0, 1);
-    let Rap = param("Rap", "ohm", 1.0, 0.0, 1000.0, 1, rap_update);
-    let Mmp = param_simple("Mmp", "kg",  1.0, 0.001, 100.0, 3);
-    let Map = param("Map", "kg / cm^2", 1.0, 0.0, 1000.0, 1, map_update);
-    let Sp = param_simple("Sp", "cm^2", 10.0, 0.0, 1000.0, 1);
+    let Vap = param("Vap", liter(), 1.0, 0.0, 1000.0, 2, vap_update);
+    let Cmp = param_simple("Cmp", mm_per_n(), 1.0, 0.01, 10.0, 3);
+    let Cap = param("Cap", cm5_per_n(), 1.0, 0.0, 100000.0, 1, cap_update);
+    let Rmp = param_simple("Rmp", n_s_per_m(), 4.0, 0.0, 100.0, 2);
+    let Rap = param("Rap", pa_s_per_m3(), 1.0, 0.0, 100000000.0, 0, rap_update);
+    let Mmp = param_simple("Mmp", g(), 100.0, 1.0, 10000.0, 1);
+    let Map = param("Map", kg_per_m4(), 1.0, 0.0, 10000000.0, 1, map_update);
+    let Sp = param_simple("Sp", cm2(), 10.0, 1.0, 1000.0, 1);
+    let Xmaxp = param_simple("Xmaxp", mm(), 10.0, 0.0, 100.0, 2);
 
     // Passive radiator mid level parameters
-    let Qmp = param("Qmp", "", 0.5, 0.0, 30.0, 1, qmp_update);
-    let ωp = param("ωp", "Hz", 20.0, 0.0, 1000.0, 1, ωp_update);
-    let Fp = param("Fp", "Hz", 120.0, 0.0, 6282.0, 1, fp_update);
-    let Tp = param("Tp", "s", 0.05, 0.0, 0.1, 1, tp_update);
+    let Qmp = param("Qmp", unitless(), 0.5, 0.0, 100.0, 2, qmp_update);
+    let ωp = param("ωp", rad_per_s(), 20.0, 0.0, 10000.0, 1, ωp_update);
+    let Fp = param("Fp", hz(), 120.0, 0.0, 1000.0, 2, fp_update);
+    let Tp = param("Tp", s(), 0.05, 0.0, 0.1, 4, tp_update);
 
     // Enclosure parameters
-    let ωb = param("ωb", "Hz", 20.0, 0.0, 1000.0, 1, ωb_update);
-    let Fb = param("Fb", "Hz", 120.0, 0.0, 6282.0, 1, fb_update);
-    let Tb = param("Tb", "s", 0.05, 0.0, 0.1, 1, tb_update);
-
-    let α = param("α", "", 3.0, 0.0, 100.0, 1, α_update);
-    let δ = param("δ", "", 7.0, 0.0, 100.0, 1, δ_update);
-    let y = param("y", "", 0.5, 0.0, 100.0, 1, y_update);
-    let h = param("h", "", 0.5, 0.0, 100.0, 1, h_update);
-    let η0 = param("η0", "", 0.4, 0.0, 100.0, 1, η0_update);
+    let ωb = param("ωb", rad_per_s(), 20.0, 0.0, 10000.0, 1, ωb_update);
+    let Fb = param("Fb", hz(), 120.0, 0.0, 1000.0, 1, fb_update);
+    let Tb = param("Tb", s(), 0.05, 0.0, 0.1, 4, tb_update);
+
+    // Sealed box parameters
+    let Fc = param("Fc", hz(), 60.0, 0.0, 1000.0, 1, fc_update);
+    let Qtc = param("Qtc", unitless(), 0.7, 0.0, 30.0, 2, qtc_update);
+
+    // Box loss parameters, as Q at the box resonance
+    let QL = param_simple("QL", unitless(), 10.0, 1.0, 1000.0, 1);
+    let QA = param_simple("QA", unitless(), 100.0, 1.0, 1000.0, 1);
+    let QP = param_simple("QP", unitless(), 50.0, 1.0, 1000.0, 1);
+    let QB = param("QB", unitless(), 7.7, 0.0, 1000.0, 2, qb_update);
+    let Γ = param("Γ", unitless(), 0.2, 0.0, 10.0, 3, Γ_update);
+
+    // Vent parameters
+    let Nv = param_simple("Nv", unitless(), 1.0, 1.0, 8.0, 0);
+    let Dv = param_simple("Dv", cm(), 5.0, 0.5, 30.0, 1);
+    let Wv = param_simple("Wv", cm(), 0.0, 0.0, 50.0, 1);
+    let Hv = param_simple("Hv", cm(), 0.0, 0.0, 50.0, 1);
+    let Lv = param_simple("Lv", cm(), 15.0, 0.0, 200.0, 1);
+    let Nf = param_simple("Nf", unitless(), 1.0, 0.0, 2.0, 0);
+    let Sv = param("Sv", cm2(), 19.6, 0.0, 2500.0, 1, sv_update);
+    let Lve = param("Lve", cm(), 17.0, 0.0, 300.0, 1, lve_update);
+    let Mav = param("Mav", kg_per_m4(), 100.0, 0.0, 10000000.0, 1, mav_update);
+    let Fv = param("Fv", hz(), 30.0, 0.0, 1000.0, 1, fv_update);
+
+    // Bandpass parameters. The rear chamber is only vented in the 6th order model. Each chamber's
+    // round ports tune it to Fr or Ff, with their ends corrected by Nf like the vented box ports
+    let Vr = param_simple("Vr", liter(), 10.0, 0.1, 1000.0, 1);
+    let Vf = param_simple("Vf", liter(), 10.0, 0.1, 1000.0, 1);
+    let Nvr = param_simple("Nvr", unitless(), 1.0, 1.0, 8.0, 0);
+    let Dvr = param_simple("Dvr", cm(), 4.0, 0.5, 30.0, 1);
+    let Lvr = param_simple("Lvr", cm(), 39.2, 0.0, 200.0, 1);
+    let Svr = param("Svr", cm2(), 12.6, 0.0, 2500.0, 1, svr_update);
+    let Nvf = param_simple("Nvf", unitless(), 1.0, 1.0, 8.0, 0);
+    let Dvf = param_simple("Dvf", cm(), 5.0, 0.5, 30.0, 1);
+    let Lvf = param_simple("Lvf", cm(), 12.8, 0.0, 200.0, 1);
+    let Svf = param("Svf", cm2(), 19.6, 0.0, 2500.0, 1, svf_update);
+    let Car = param("Car", cm5_per_n(), 710.0, 0.0, 10000000.0, 1, car_update);
+    let Caf = param("Caf", cm5_per_n(), 710.0, 0.0, 10000000.0, 1, caf_update);
+    let Mar = param("Mar", kg_per_m4(), 400.0, 0.0, 10000000.0, 1, mar_update);
+    let Maf = param("Maf", kg_per_m4(), 100.0, 0.0, 10000000.0, 1, maf_update);
+    let Fr = param("Fr", hz(), 30.0, 0.0, 1000.0, 1, fr_update);
+    let Ff = param("Ff", hz(), 60.0, 0.0, 1000.0, 1, ff_update);
+
+    // Voice coil parameters. L2 and R2 in parallel follow Le in the LR-2 semi-inductance model,
+    // and Wright's model replaces Le
+    let Le = param_simple("Le", mh(), 0.5, 0.0, 100.0, 3);
+    let L2 = param_simple("L2", mh(), 0.3, 0.0, 100.0, 3);
+    let R2 = param_simple("R2", ohm(), 2.0, 0.0, 1000.0, 2);
+    let Krm = param_simple("Krm", ohm(), 0.004, 0.0, 10.0, 4);
+    let Erm = param_simple("Erm", unitless(), 0.7, 0.0, 1.0, 3);
+    let Kxm = param_simple("Kxm", ohm(), 0.007, 0.0, 10.0, 4);
+    let Exm = param_simple("Exm", unitless(), 0.7, 0.0, 1.0, 3);
+
+    // Drive parameters. SPL is calculated for Vin at distance r, and the tone burst response for
+    // Ntb cycles at Ftb
+    let Vin = param_simple("Vin", volt(), 2.83, 0.01, 1000.0, 2);
+    let Pin = param("Pin", watt(), 1.0, 0.0, 100000.0, 2, pin_update);
+    let r = param_simple("r", m(), 1.0, 0.1, 100.0, 2);
+    let Ftb = param_simple("Ftb", hz(), 40.0, 1.0, 20000.0, 1);
+    let Ntb = param_simple("Ntb", unitless(), 4.0, 1.0, 100.0, 0);
+
+    let α = param("α", unitless(), 3.0, 0.0, 100.0, 2, α_update);
+    let δ = param("δ", unitless(), 7.0, 0.0, 100.0, 2, δ_update);
+    let y = param("y", unitless(), 0.5, 0.0, 100.0, 2, y_update);
+    let h = param("h", unitless(), 0.5, 0.0, 100.0, 2, h_update);
+    let η0 = param("η0", unitless(), 0.4, 0.0, 1.0, 4, η0_update);
 
     let p_arr = vec![Xmax.clone(), Vd.clone(), Sd.clone(), Bl.clone(), Re.clone(), Mmd.clone(), Mms.clone(),
-                 Mas.clone(), Rms.clone(), Ras.clone(), Cms.clone(), Cas.clone(), Vas.clone(), Rg.clone(),
-                 Ts.clone(), ωs.clone(), Fs.clone(), Qes.clone(), Qms.clone(), Qts.clone(), Qs.clone(),
+                 Mas.clone(), Rms.clone(), Ras.clone(), Cms.clone(), Cas.clone(), Vas.clone(), Rg.clone(), Pe.clone(),
+                 Ts.clone(), ωs.clone(), Fs.clone(), Qes.clone(), Qe.clone(), Qms.clone(), Qts.clone(), Qs.clone(),
                  Cab.clone(), Vb.clone(),
                  Vap.clone(), Cmp.clone(), Cap.clone(), Rmp.clone(), Rap.clone(), Mmp.clone(),
-                 Map.clone(), Sp.clone(), Qmp.clone(), ωp.clone(), Fp.clone(), Tp.clone(),
-                 ωb.clone(), Fb.clone(), Tb.clone(),
+                 Map.clone(), Sp.clone(), Xmaxp.clone(), Qmp.clone(), ωp.clone(), Fp.clone(), Tp.clone(),
+                 ωb.clone(), Fb.clone(), Tb.clone(), Fc.clone(), Qtc.clone(),
+                 QL.clone(), QA.clone(), QP.clone(), QB.clone(), Γ.clone(),
+                 Nv.clone(), Dv.clone(), Wv.clone(), Hv.clone(), Lv.clone(), Nf.clone(),
+                 Sv.clone(), Lve.clone(), Mav.clone(), Fv.clone(),
+                 Vr.clone(), Vf.clone(), Nvr.clone(), Dvr.clone(), Lvr.clone(), Svr.clone(),
+                 Nvf.clone(), Dvf.clone(), Lvf.clone(), Svf.clone(),
+                 Car.clone(), Caf.clone(), Mar.clone(), Maf.clone(), Fr.clone(), Ff.clone(),
+                 Le.clone(), L2.clone(), R2.clone(),
+                 Krm.clone(), Erm.clone(), Kxm.clone(), Exm.clone(),
+                 Vin.clone(), Pin.clone(), r.clone(), Ftb.clone(), Ntb.clone(),
                  α.clone(), δ.clone(), y.clone(), h.clone(), η0.clone(),
                  ρ0.clone(), c.clone(), t.clone()];
 
@@ -151,13 +610,22 @@ pub fn builtin_defaults() -> Parameters {
         param_map: p_map,
 
         driver: [Xmax.clone(), Vd.clone(), Sd.clone(), Bl.clone(), Re.clone(), Mmd.clone(), Mms.clone(),
-                 Mas.clone(), Rms.clone(), Ras.clone(), Cms.clone(), Cas.clone(), Vas.clone(), Rg.clone(),
-                 Ts.clone(), ωs.clone(), Fs.clone(), Qes.clone(), Qms.clone(), Qts.clone(), Qs.clone(),
+                 Mas.clone(), Rms.clone(), Ras.clone(), Cms.clone(), Cas.clone(), Vas.clone(), Rg.clone(), Pe.clone(),
+                 Ts.clone(), ωs.clone(), Fs.clone(), Qes.clone(), Qe.clone(), Qms.clone(), Qts.clone(), Qs.clone(),
                  Cab.clone(), Vb.clone()],
         passive: [Vap.clone(), Cmp.clone(), Cap.clone(), Rmp.clone(), Rap.clone(), Mmp.clone(),
-                  Map.clone(), Sp.clone(), Qmp.clone(), ωp.clone(), Fp.clone(), Tp.clone()],
-        enclosure: [ωb.clone(), Fb.clone(), Tb.clone(),
+                  Map.clone(), Sp.clone(), Xmaxp.clone(), Qmp.clone(), ωp.clone(), Fp.clone(), Tp.clone()],
+        enclosure: [ωb.clone(), Fb.clone(), Tb.clone(), Fc.clone(), Qtc.clone(),
+                    QL.clone(), QA.clone(), QP.clone(), QB.clone(), Γ.clone(),
                     α.clone(), δ.clone(), y.clone(), h.clone(), η0.clone()],
+        vent: [Nv.clone(), Dv.clone(), Wv.clone(), Hv.clone(), Lv.clone(), Nf.clone(),
+               Sv.clone(), Lve.clone(), Mav.clone(), Fv.clone()],
+        bandpass: [Vr.clone(), Vf.clone(), Nvr.clone(), Dvr.clone(), Lvr.clone(), Svr.clone(),
+                   Nvf.clone(), Dvf.clone(), Lvf.clone(), Svf.clone(),
+                   Car.clone(), Caf.clone(), Mar.clone(), Maf.clone(), Fr.clone(), Ff.clone()],
+        voice_coil: [Le.clone(), L2.clone(), R2.clone(),
+                     Krm.clone(), Erm.clone(), Kxm.clone(), Exm.clone()],
+        drive: [Vin.clone(), Pin.clone(), r.clone(), Ftb.clone(), Ntb.clone()],
         constant: [ρ0.clone(), c.clone(), t.clone()],
 
         // Environmental parameters
@@ -165,20 +633,32 @@ pub fn builtin_defaults() -> Parameters {
 
         // Driver low level parameters
         Xmax, Vd, Sd, Bl, Re, Mmd, Mms,
-        Mas, Rms, Ras, Cms, Cas, Vas, Rg,
+        Mas, Rms, Ras, Cms, Cas, Vas, Rg, Pe,
 
         // Driver mid level parameters
-        Ts, ωs, Fs, Qes, Qms, Qts, Qs,
+        Ts, ωs, Fs, Qes, Qe, Qms, Qts, Qs,
         Cab, Vb,
 
         // Passive radiator low level parameters
-        Vap, Cmp, Cap, Rmp, Rap, Mmp, Map, Sp,
+        Vap, Cmp, Cap, Rmp, Rap, Mmp, Map, Sp, Xmaxp,
 
         // Passive radiator mid level parameters
         Qmp, ωp, Fp, Tp,
 
         // Enclosure parameters
-        ωb, Fb, Tb, α, δ, y, h, η0,
+        ωb, Fb, Tb, Fc, Qtc, QL, QA, QP, QB, Γ, α, δ, y, h, η0,
+
+        // Vent parameters
+        Nv, Dv, Wv, Hv, Lv, Nf, Sv, Lve, Mav, Fv,
+
+        // Bandpass parameters
+        Vr, Vf, Nvr, Dvr, Lvr, Svr, Nvf, Dvf, Lvf, Svf, Car, Caf, Mar, Maf, Fr, Ff,
+
+        // Voice coil parameters
+        Le, L2, R2, Krm, Erm, Kxm, Exm,
+
+        // Drive parameters
+        Vin, Pin, r, Ftb, Ntb,
     };
     
     set_children(&mut P.Vd, vec![P.Sd.clone(), P.Xmax.clone()]);
@@ -191,9 +671,10 @@ pub fn builtin_defaults() -> Parameters {
     set_children(&mut P.ωs, vec![P.Fs.clone()]);
     set_children(&mut P.Fs, vec![P.Mas.clone(), P.Cas.clone()]);
     set_children(&mut P.Qes, vec![P.ωs.clone(), P.Re.clone(), P.Mas.clone(), P.Sd.clone(), P.Bl.clone()]);
-    set_children(&mut P.Qms, vec![P.Bl.clone(), P.Cas.clone(), P.Ras.clone()]);
+    set_children(&mut P.Qms, vec![P.ωs.clone(), P.Cas.clone(), P.Ras.clone()]);
     set_children(&mut P.Qts, vec![P.Qes.clone(), P.Qms.clone()]);
-    set_children(&mut P.Qs, vec![P.Qts.clone()]);
+    set_children(&mut P.Qe, vec![P.Qes.clone(), P.Re.clone(), P.Rg.clone()]);
+    set_children(&mut P.Qs, vec![P.Qe.clone(), P.Qms.clone()]);
     set_children(&mut P.Vb, vec![P.ρ0.clone(), P.c.clone(), P.Cab.clone()]);
     set_children(&mut P.Vap, vec![P.ρ0.clone(), P.c.clone(), P.Cap.clone()]);
     set_children(&mut P.Cap, vec![P.Cmp.clone(), P.Sp.clone()]);
@@ -204,13 +685,219 @@ pub fn builtin_defaults() -> Parameters {
     set_children(&mut P.Tp, vec![P.ωp.clone()]);
     set_children(&mut P.ωp, vec![P.Fp.clone()]);
     set_children(&mut P.Fb, vec![P.Cab.clone(), P.Cap.clone(), P.Map.clone()]);
-    set_children(&mut P.Tb, vec![P.ωp.clone()]);
-    set_children(&mut P.ωb, vec![P.Fp.clone()]);
+    set_children(&mut P.Tb, vec![P.ωb.clone()]);
+    set_children(&mut P.ωb, vec![P.Fb.clone()]);
+    set_children(&mut P.Fc, vec![P.Fs.clone(), P.α.clone()]);
+    set_children(&mut P.Qtc, vec![P.Qs.clone(), P.α.clone()]);
+    set_children(&mut P.QB, vec![P.QL.clone(), P.QA.clone(), P.QP.clone()]);
+    set_children(&mut P.Γ, vec![P.h.clone(), P.QB.clone()]);
+    set_children(&mut P.Sv, vec![P.Dv.clone(), P.Wv.clone(), P.Hv.clone()]);
+    set_children(&mut P.Lve, vec![P.Lv.clone(), P.Sv.clone(), P.Nf.clone()]);
+    set_children(&mut P.Mav, vec![P.ρ0.clone(), P.Lve.clone(), P.Nv.clone(), P.Sv.clone()]);
+    set_children(&mut P.Fv, vec![P.Mav.clone(), P.Cab.clone()]);
+    set_children(&mut P.Car, vec![P.Vr.clone(), P.ρ0.clone(), P.c.clone()]);
+    set_children(&mut P.Caf, vec![P.Vf.clone(), P.ρ0.clone(), P.c.clone()]);
+    set_children(&mut P.Svr, vec![P.Dvr.clone()]);
+    set_children(&mut P.Svf, vec![P.Dvf.clone()]);
+    set_children(&mut P.Mar, vec![P.ρ0.clone(), P.Lvr.clone(), P.Nvr.clone(), P.Svr.clone(), P.Nf.clone()]);
+    set_children(&mut P.Maf, vec![P.ρ0.clone(), P.Lvf.clone(), P.Nvf.clone(), P.Svf.clone(), P.Nf.clone()]);
+    set_children(&mut P.Fr, vec![P.Mar.clone(), P.Car.clone()]);
+    set_children(&mut P.Ff, vec![P.Maf.clone(), P.Caf.clone()]);
+    set_children(&mut P.Pin, vec![P.Vin.clone(), P.Re.clone(), P.Rg.clone()]);
     set_children(&mut P.α, vec![P.Cas.clone(), P.Cab.clone()]);
     set_children(&mut P.δ, vec![P.Cap.clone(), P.Cab.clone()]);
-    set_children(&mut P.y, vec![P.Fb.clone(), P.Fs.clone()]);
-    set_children(&mut P.h, vec![P.Fb.clone(), P.Fp.clone()]);
-    set_children(&mut P.η0, vec![P.c.clone(), P.Fs.clone(), P.Vas.clone(), P.Qes.clone()]);
+    set_children(&mut P.y, vec![P.Fp.clone(), P.Fs.clone()]);
+    set_children(&mut P.h, vec![P.Fb.clone(), P.Fs.clone()]);
+    set_children(&mut P.η0, vec![P.c.clone(), P.Fs.clone(), P.Vas.clone(), P.Qe.clone()]);
 
-    P
+    P.validate()?;
+    P.update_all();
+    Ok(P)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn loads_range_and_converts_units() {
+        let path = ::std::env::temp_dir().join("bass-calc-load-range.bass");
+        fs::write(&path, "Name, Default, Min, Max, Prec., Units\n\
+                          Xmax, 0.5, 0, 4, 2, in\n\
+                          Sd, 3, 1, 5, 1, L\n").unwrap();
+        let P = builtin_defaults().unwrap();
+        let problems = load_file(&P, &path, LoadMode::Lenient).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].column, Some(5));
+        assert!((P.Xmax.v() - 0.0127).abs() < 1e-12);
+        assert!((P.Xmax.max() - 0.1016).abs() < 1e-12);
+        assert_eq!(P.Xmax.min(), 0.0);
+
+        // Volume is not an area, so Sd keeps its built in value and range
+        assert!((P.Sd.display_value() - 10.0).abs() < 1e-12);
+        assert!((P.Sd.max() - 0.1).abs() < 1e-12);
+    }
+
+    #[test]
+    fn reports_problems() {
+        let path = ::std::env::temp_dir().join("bass-calc-load-problems.bass");
+        fs::write(&path, "Name, Default, Min, Max, Prec., Units\n\
+                          Xmax, 5, 0, 4, 2, mm\n\
+                          Sd, 90, 1, 1000, x, cm^2\n\
+                          Woofer, 1, 0, 2, 1, mm\n\
+                          Re, 3.4\n").unwrap();
+
+        let P = builtin_defaults().unwrap();
+        match load_file(&P, &path, LoadMode::Strict) {
+            Err(LoadError::Invalid { problems, .. }) => assert_eq!(problems.len(), 4),
+            _ => panic!("strict load should fail"),
+        }
+        assert!((P.Sd.display_value() - 10.0).abs() < 1e-12);
+
+        let problems = load_file(&P, &path, LoadMode::Lenient).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(problems.iter().map(|p| p.to_string()).collect::<Vec<_>>(), vec![
+            "line 2, Default column (Xmax): 5 is outside the range 0 to 4 mm",
+            "line 3, Prec. column (Sd): 'x' is not a whole number",
+            "line 4, Name column (Woofer): unknown parameter",
+            "line 5 (Re): expected 6 fields, found 2",
+        ]);
+        assert!((P.Xmax.v() - 0.004).abs() < 1e-12);
+        assert!((P.Sd.display_value() - 90.0).abs() < 1e-12);
+
+        let missing = ::std::env::temp_dir().join("bass-calc-missing.bass");
+        match load_file(&P, &missing, LoadMode::Lenient) {
+            Err(LoadError::Io { .. }) => (),
+            _ => panic!("missing file should fail to load"),
+        }
+    }
+
+    #[test]
+    fn reset_clears_earlier_preset() {
+        let path = ::std::env::temp_dir().join("bass-calc-reset.bass");
+        fs::write(&path, "Name, Default, Min, Max, Prec., Units\n\
+                          Xmax, 20, 0, 50, 3, mm\n").unwrap();
+        let P = builtin_defaults().unwrap();
+        load_file(&P, &path, LoadMode::Strict).unwrap();
+        fs::remove_file(&path).unwrap();
+        P.set(&P.Sd, 0.02);
+
+        let defaults = builtin_defaults().unwrap();
+        P.reset(&defaults);
+        assert_eq!((P.Xmax.v(), P.Xmax.max(), P.Xmax.precision()),
+                   (defaults.Xmax.v(), defaults.Xmax.max(), defaults.Xmax.precision()));
+        assert_eq!((P.Sd.v(), P.Vd.v()), (defaults.Sd.v(), defaults.Vd.v()));
+    }
+
+    #[test]
+    fn loads_prototype_defaults() {
+        // The prototype's table has no Prec. column, and no Units for dimensionless parameters
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../save_files/defaults.bass");
+        let P = builtin_defaults().unwrap();
+        P.Rg.set_range(1.0, 10.0);
+        P.set(&P.Rg, 5.0);
+        let precision = P.Rg.precision();
+        let problems = load_file(&P, &path, LoadMode::Lenient).unwrap();
+
+        // Only the prototype's own mistakes are left: acoustic quantities in mechanical units, and
+        // times outside their range
+        let names = problems.iter().map(|p| p.param.clone().unwrap()).collect::<Vec<String>>();
+        assert_eq!(names, vec!["Mas", "Ras", "Ts", "Rap", "Map", "Tp", "Tb"]);
+        assert!((P.Xmax.v() - 0.00925).abs() < 1e-12);
+        assert!((P.Sd.v() - 0.0094).abs() < 1e-12);
+        assert!((P.Bl.v() - 7.17).abs() < 1e-12);
+        assert_eq!((P.Rg.v(), P.Rg.max(), P.Rg.precision()), (0.0, 100.0, precision));
+        assert!((P.Mmp.max() - 10.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn imports_python_save_file() {
+        let path = ::std::env::temp_dir().join("bass-calc-python.bass");
+        fs::write(&path, "Xmax=9.25 millimeter\n\
+                          Sd=94 centimeter ** 2\n\
+                          Rms=<Quantity(2.55, 'newton * second / meter')>\n\
+                          Qes=0.58 dimensionless\n\
+                          ωs=50.0 1 / second\n\
+                          Ras=1 ohm\n").unwrap();
+
+        let P = builtin_defaults().unwrap();
+        let problems = load_file(&P, &path, LoadMode::Lenient).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(problems.iter().map(|p| p.to_string()).collect::<Vec<_>>(), vec![
+            "line 6 (Ras): 'ohm' is not compatible with Pa*s/m^3",
+        ]);
+        assert!((P.Xmax.v() - 0.00925).abs() < 1e-12);
+        assert!((P.Sd.v() - 0.0094).abs() < 1e-12);
+        assert!((P.Rms.v() - 2.55).abs() < 1e-12);
+    }
+
+    #[test]
+    fn save_round_trip() {
+        let path = ::std::env::temp_dir().join("bass-calc-save-new.bass");
+        let _ = fs::remove_file(&path);
+        let P = builtin_defaults().unwrap();
+        P.set(&P.Xmax, 0.0042);
+        P.Xmax.set_range(0.001, 0.02);
+        save_file(&P, &path).unwrap();
+
+        let Q = builtin_defaults().unwrap();
+        assert_eq!(load_file(&Q, &path, LoadMode::Strict).unwrap(), vec![]);
+        fs::remove_file(&path).unwrap();
+        assert!((Q.Xmax.v() - 0.0042).abs() < 1e-12);
+        assert!((Q.Xmax.min() - 0.001).abs() < 1e-12);
+        assert!((Q.Xmax.max() - 0.02).abs() < 1e-12);
+        for (p, q) in P.driver.iter().zip(Q.driver.iter()) {
+            assert_eq!(*p.display(), *q.display(), "{}", p.name);
+        }
+    }
+
+    #[test]
+    fn save_keeps_full_values() {
+        // More digits than Mmp's precision of 1 g shows
+        let path = ::std::env::temp_dir().join("bass-calc-save-full.bass");
+        let _ = fs::remove_file(&path);
+        let P = builtin_defaults().unwrap();
+        P.set(&P.Mmp, 0.0123456);
+        P.set(&P.Cab, P.Cab.v() * 1.23456789);
+        save_file(&P, &path).unwrap();
+
+        let Q = builtin_defaults().unwrap();
+        assert_eq!(load_file(&Q, &path, LoadMode::Strict).unwrap(), vec![]);
+        fs::remove_file(&path).unwrap();
+        for &(p, q) in [(&*P.Mmp, &*Q.Mmp), (&*P.Cab, &*Q.Cab), (&*P.Vb, &*Q.Vb), (&*P.Fb, &*Q.Fb)].iter() {
+            assert!((q.v() / p.v() - 1.0).abs() < 1e-9, "{} {} {}", p.name, p.v(), q.v());
+        }
+    }
+
+    #[test]
+    fn save_keeps_layout() {
+        let path = ::std::env::temp_dir().join("bass-calc-save-existing.bass");
+        fs::write(&path, "Name, Default,  Min,    Max,       Prec.,  Units\n\
+                          \n\
+                          # Driver\n\
+                          Xmax, 9.25,     0,      100,       2,      mm\n\
+                          Qes,  0.39,     0,      30,        2,").unwrap();
+        let P = builtin_defaults().unwrap();
+        P.set(&P.Xmax, 0.0031);
+        save_file(&P, &path).unwrap();
+
+        let saved = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let lines = saved.lines().collect::<Vec<&str>>();
+        assert_eq!(lines[..5].to_vec(), vec![
+            "Name, Default,  Min,    Max,       Prec.,  Units",
+            "",
+            "# Driver",
+            "Xmax, 3.1,      0,      100,       1,      mm",
+            // A long value pushes the next field along, and the columns after it line up again
+            &*format!("Qes,  {}, 0, 30,        2,", format_number(P.Qes.display_value())),
+        ]);
+        // Parameters missing from the file are added at the end
+        assert!(lines.iter().any(|line| line.starts_with("Sd,")));
+        assert!(!saved.ends_with('\n'));
+    }
 }
@@ -0,0 +1,200 @@
+//! Validation of the dependency graph built by `set_children`
+//!
+//! The child lists are the declared inputs of each update fn, and `validate` checks them for
+//! cycles. In tests, `ParamPrivate::v` also records which parameters each update fn reads, so the
+//! tests can compare the reads with the child lists at several sets of values and catch inputs
+//! read on only one branch. Outside tests `v` is a plain read
+
+use parameters::params::*;
+use std::collections::HashMap;
+use std::fmt;
+
+#[cfg(test)]
+use std::cell::{Cell, RefCell};
+
+#[cfg(test)]
+thread_local! {
+    static TRACING: Cell<bool> = Cell::new(false);
+    static READS: RefCell<Vec<String>> = RefCell::new(vec![]);
+}
+
+/// Record that the parameter `name` was read, if an update fn is being traced
+#[cfg(test)]
+pub fn record_read(name: &str) {
+    if TRACING.with(|tracing| tracing.get()) {
+        READS.with(|reads| reads.borrow_mut().push(name.to_string()));
+    }
+}
+
+/// Names of every parameter read by `param`'s update fn, sorted and without duplicates
+#[cfg(test)]
+pub fn traced_reads(params: &Parameters, param: &Param) -> Vec<String> {
+    let update_fn = match param.update_fn {
+        Some(ref update_fn) => update_fn,
+        None => return vec![],
+    };
+
+    READS.with(|reads| reads.borrow_mut().clear());
+    TRACING.with(|tracing| tracing.set(true));
+    let _ = update_fn(params);
+    TRACING.with(|tracing| tracing.set(false));
+    let mut names = READS.with(|reads| reads.replace(vec![]));
+
+    names.sort();
+    names.dedup();
+    names
+}
+
+#[derive(Debug, PartialEq)]
+pub struct GraphError {
+    /// Each cycle as a list of parameter names, starting and ending with the same parameter
+    pub cycles: Vec<Vec<String>>,
+}
+
+impl fmt::Display for GraphError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Invalid parameter dependency graph:")?;
+        for cycle in self.cycles.iter() {
+            writeln!(f, "  cycle: {}", cycle.join(" -> "))?;
+        }
+        Ok(())
+    }
+}
+
+impl Parameters {
+
+    /// Check that the graph has no cycles
+    pub fn validate(&self) -> Result<(), GraphError> {
+        let mut names = self.param_map.keys().cloned().collect::<Vec<String>>();
+        names.sort();
+
+        let cycles = self.find_cycles(&names);
+        if cycles.is_empty() {
+            Ok(())
+        } else {
+            Err(GraphError { cycles })
+        }
+    }
+
+    /// Depth first search along `children` links, reporting each back edge as a cycle
+    fn find_cycles(&self, names: &[String]) -> Vec<Vec<String>> {
+
+        #[derive(Clone, Copy, PartialEq)]
+        enum Mark { Visiting, Done }
+
+        fn visit(param: &Param, marks: &mut HashMap<String, Mark>, stack: &mut Vec<String>,
+                 cycles: &mut Vec<Vec<String>>) {
+
+            match marks.get(&param.name).cloned() {
+                Some(Mark::Done) => return,
+                Some(Mark::Visiting) => {
+                    let start = stack.iter().position(|name| *name == param.name).unwrap();
+                    let mut cycle = stack[start..].to_vec();
+                    cycle.push(param.name.clone());
+                    cycles.push(cycle);
+                    return;
+                },
+                None => (),
+            }
+            marks.insert(param.name.clone(), Mark::Visiting);
+            stack.push(param.name.clone());
+            for child in param.children().iter() {
+                visit(child, marks, stack, cycles);
+            }
+            stack.pop();
+            marks.insert(param.name.clone(), Mark::Done);
+        }
+
+        let mut marks = HashMap::new();
+        let mut stack = vec![];
+        let mut cycles = vec![];
+        for name in names {
+            visit(&self.param_map[name], &mut marks, &mut stack, &mut cycles);
+        }
+        cycles
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use parameters::builtin_defaults;
+
+    /// Difference between what a parameter's update fn reads and its listed children
+    #[derive(Debug, PartialEq)]
+    struct DependencyMismatch {
+        param: String,
+        /// Read by the update fn but not listed as children
+        missing: Vec<String>,
+        /// Listed as children but never read by the update fn
+        extra: Vec<String>,
+    }
+
+    // Parameters whose children don't match what their update fns read at the current values
+    fn mismatches(P: &Parameters) -> Vec<DependencyMismatch> {
+        let mut names = P.param_map.keys().cloned().collect::<Vec<String>>();
+        names.sort();
+
+        let mut mismatches = vec![];
+        for name in names.iter() {
+            let param = &P.param_map[name];
+            let reads = traced_reads(P, param);
+            let children = param.children().iter().map(|p| p.name.clone()).collect::<Vec<String>>();
+
+            let missing = reads.iter().filter(|r| !children.contains(r)).cloned().collect::<Vec<_>>();
+            let extra = children.iter().filter(|c| !reads.contains(c)).cloned().collect::<Vec<_>>();
+
+            if !missing.is_empty() || !extra.is_empty() {
+                mismatches.push(DependencyMismatch { param: name.clone(), missing, extra });
+            }
+        }
+        mismatches
+    }
+
+    #[test]
+    fn children_match_reads() {
+        // At the defaults, and with every input at the bottom and then the top of its range, so
+        // that update fns which branch take each branch
+        let P = builtin_defaults().unwrap();
+        assert_eq!(mismatches(&P), vec![]);
+        let inputs = P.param_map.values().filter(|p| p.update_fn.is_none()).cloned().collect::<Vec<Param>>();
+        for &top in [false, true].iter() {
+            for input in inputs.iter() {
+                input.set(if top { input.max() } else { input.min() });
+            }
+            P.update_all();
+            assert_eq!(mismatches(&P), vec![]);
+        }
+    }
+
+    #[test]
+    fn reports_mismatch_and_cycle() {
+        let mut P = builtin_defaults().unwrap();
+        set_children(&mut P.Sd, vec![P.Vd.clone()]);
+
+        assert_eq!(mismatches(&P), vec![DependencyMismatch {
+            param: "Sd".to_string(),
+            missing: vec![],
+            extra: vec!["Vd".to_string()],
+        }]);
+        let err = P.validate().unwrap_err();
+        assert_eq!(err.cycles.len(), 1);
+        assert!(err.cycles[0].contains(&"Sd".to_string()) && err.cycles[0].contains(&"Vd".to_string()));
+    }
+
+    #[test]
+    fn traces_update_reads() {
+        let P = builtin_defaults().unwrap();
+        assert_eq!(traced_reads(&P, &P.Mms), vec!["Mmd", "Sd", "ρ0"]);
+        assert_eq!(traced_reads(&P, &P.Sd), Vec::<String>::new());
+
+        // Reads outside tracing aren't recorded, and a branching update fn is seen to read all of
+        // its inputs whichever branch it takes
+        let _ = P.Mms.v();
+        assert!(READS.with(|reads| reads.borrow().is_empty()));
+        assert_eq!(traced_reads(&P, &P.Sv), vec!["Dv", "Hv", "Wv"]);
+        P.set(&P.Wv, 0.1);
+        P.set(&P.Hv, 0.05);
+        assert_eq!(traced_reads(&P, &P.Sv), vec!["Dv", "Hv", "Wv"]);
+    }
+}
@@ -0,0 +1,424 @@
+//! Explicit inversions of the update fns in `params.rs`
+//!
+//! Each `Inversion` computes the value an input parameter must take so that a derived parameter
+//! reaches a requested target, with every other input held constant. Values are in SI base units
+
+use parameters::params::*;
+use parameters::units::*;
+use std::f64::consts::PI;
+use std::f64;
+use std::fmt;
+
+const PI2: f64 = 2.0 * PI;
+
+/// Solves for `input` given a requested value of `target`, both in SI base units
+pub struct Inversion {
+    pub target: &'static str,
+    pub input: &'static str,
+    pub solve: fn(&Parameters, f64) -> f64,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SolveError {
+    /// No inversion solves `target` by adjusting `input`
+    NoInversion { target: String, input: String },
+    /// The target cannot be reached by adjusting `input` alone
+    Unreachable { target: String, input: String, value: f64 },
+    /// The required input value lies outside the input's range, in the input's display unit
+    OutOfRange { input: String, value: f64, min: f64, max: f64, unit: String },
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SolveError::NoInversion { ref target, ref input } => {
+                write!(f, "{} cannot be solved by adjusting {}", target, input)
+            },
+            SolveError::Unreachable { ref target, ref input, value } => {
+                write!(f, "{} = {} cannot be reached by adjusting {}", target, value, input)
+            },
+            SolveError::OutOfRange { ref input, value, min, max, ref unit } => {
+                write!(f, "{} would need to be {} {}, outside {} to {}", input, value, unit, min, max)
+            },
+        }
+    }
+}
+
+pub static INVERSIONS: [Inversion; 32] = [
+    Inversion { target: "Mms", input: "Mmd", solve: mmd_for_mms },
+    Inversion { target: "Fs", input: "Cms", solve: cms_for_fs },
+    Inversion { target: "Fs", input: "Mmd", solve: mmd_for_fs },
+    Inversion { target: "Vas", input: "Cms", solve: cms_for_vas },
+    Inversion { target: "Qes", input: "Bl", solve: bl_for_qes },
+    Inversion { target: "Qes", input: "Re", solve: re_for_qes },
+    Inversion { target: "Qe", input: "Rg", solve: rg_for_qe },
+    Inversion { target: "Qms", input: "Rms", solve: rms_for_qms },
+    Inversion { target: "Qts", input: "Bl", solve: bl_for_qts },
+    Inversion { target: "Qts", input: "Rms", solve: rms_for_qts },
+    Inversion { target: "Vb", input: "Cab", solve: cab_for_vb },
+    Inversion { target: "Vap", input: "Cmp", solve: cmp_for_vap },
+    Inversion { target: "Fp", input: "Mmp", solve: mmp_for_fp },
+    Inversion { target: "Fp", input: "Cmp", solve: cmp_for_fp },
+    Inversion { target: "Qmp", input: "Rmp", solve: rmp_for_qmp },
+    Inversion { target: "Fb", input: "Cab", solve: cab_for_fb },
+    Inversion { target: "Fb", input: "Mmp", solve: mmp_for_fb },
+    Inversion { target: "Fc", input: "Cab", solve: cab_for_fc },
+    Inversion { target: "Qtc", input: "Cab", solve: cab_for_qtc },
+    Inversion { target: "α", input: "Cab", solve: cab_for_α },
+    Inversion { target: "QB", input: "QL", solve: ql_for_qb },
+    Inversion { target: "Γ", input: "QL", solve: ql_for_Γ },
+    Inversion { target: "Fv", input: "Lv", solve: lv_for_fv },
+    Inversion { target: "Fv", input: "Dv", solve: dv_for_fv },
+    Inversion { target: "Fv", input: "Cab", solve: cab_for_fv },
+    Inversion { target: "Fr", input: "Lvr", solve: lvr_for_fr },
+    Inversion { target: "Fr", input: "Dvr", solve: dvr_for_fr },
+    Inversion { target: "Fr", input: "Vr", solve: vr_for_fr },
+    Inversion { target: "Ff", input: "Lvf", solve: lvf_for_ff },
+    Inversion { target: "Ff", input: "Dvf", solve: dvf_for_ff },
+    Inversion { target: "Ff", input: "Vf", solve: vf_for_ff },
+    Inversion { target: "Pin", input: "Vin", solve: vin_for_pin },
+];
+
+impl Parameters {
+
+    /// All inversions that can solve for `target`, in order of preference
+    pub fn inversions(&self, target: &Param) -> Vec<&'static Inversion> {
+        INVERSIONS.iter().filter(|inv| inv.target == target.name).collect()
+    }
+
+    /// Adjust `input` so that `target` takes the value `value`, then recalculate all dependents
+    pub fn solve(&self, target: &Param, input: &str, value: f64) -> Result<(), SolveError> {
+        let inversion = INVERSIONS.iter()
+            .find(|inv| inv.target == target.name && inv.input == input)
+            .ok_or_else(|| SolveError::NoInversion {
+                target: target.name.clone(),
+                input: input.to_string(),
+            })?;
+
+        let input_param = self.get(input).unwrap();
+        let input_value = (inversion.solve)(self, value);
+
+        if !input_value.is_finite() {
+            return Err(SolveError::Unreachable {
+                target: target.name.clone(),
+                input: input.to_string(),
+                value,
+            });
+        }
+        if !input_param.in_range(input_value) {
+            let scale = input_param.unit.scale;
+            return Err(SolveError::OutOfRange {
+                input: input.to_string(),
+                value: input_value / scale,
+                min: input_param.min() / scale,
+                max: input_param.max() / scale,
+                unit: input_param.unit.symbol.clone(),
+            });
+        }
+        self.set(&input_param, input_value);
+        Ok(())
+    }
+}
+
+// Required acoustic compliance for resonance `Fs` with the current acoustic mass
+// 1 / ((kg / m^4) * Hz^2) = m^5 / N
+fn cas_for_fs(P: &Parameters, Fs: Frequency) -> AcousticCompliance {
+    let Mas: AcousticMass = P.Mas.q();
+    1.0 / (Mas * (PI2 * Fs) * (PI2 * Fs))
+}
+
+// kg - kg = kg
+fn mmd_for_mms(P: &Parameters, Mms: f64) -> f64 {
+    let Mmd: Mass = si::<dim::Mass>(Mms) - mms_air_load(P);
+    Mmd.value
+}
+
+// (m^5 / N) / m^4 = m / N
+fn cms_for_fs(P: &Parameters, Fs: f64) -> f64 {
+    let Sd: Area = P.Sd.q();
+    let Cms: MechanicalCompliance = cas_for_fs(P, si(Fs)) / (Sd * Sd);
+    Cms.value
+}
+
+// 1 / ((m^5 / N) * Hz^2) * m^4 = kg
+fn mmd_for_fs(P: &Parameters, Fs: f64) -> f64 {
+    let Fs: Frequency = si(Fs);
+    let Sd: Area = P.Sd.q();
+    let Cas: AcousticCompliance = P.Cas.q();
+    let Mms: Mass = Sd * Sd / (Cas * (PI2 * Fs) * (PI2 * Fs));
+    mmd_for_mms(P, Mms.value)
+}
+
+// m^3 / ((kg / m^3) * (m/s)^2) / m^4 = m / N
+fn cms_for_vas(P: &Parameters, Vas: f64) -> f64 {
+    let Sd: Area = P.Sd.q();
+    let Cas: AcousticCompliance = si::<dim::Volume>(Vas) / (P.ρ0.q() * P.c.q() * P.c.q());
+    let Cms: MechanicalCompliance = Cas / (Sd * Sd);
+    Cms.value
+}
+
+// sqrt((1 / s) * ohm * (kg / m^4) * m^4) = tesla * m
+fn bl_for_qes(P: &Parameters, Qes: f64) -> f64 {
+    let Sd: Area = P.Sd.q();
+    let ωs: Frequency = P.ωs.q();
+    let Re: Resistance = P.Re.q();
+    let Mas: AcousticMass = P.Mas.q();
+    let Bl: ForceFactor = ((ωs * Re * Mas * Sd * Sd) / Qes).sqrt();
+    Bl.value
+}
+
+// (tesla * m)^2 / ((1 / s) * (kg / m^4) * m^4) = ohm
+fn re_for_qes(P: &Parameters, Qes: f64) -> f64 {
+    let Sd: Area = P.Sd.q();
+    let Bl: ForceFactor = P.Bl.q();
+    let ωs: Frequency = P.ωs.q();
+    let Mas: AcousticMass = P.Mas.q();
+    let Re: Resistance = Qes * Bl * Bl / (ωs * Mas * Sd * Sd);
+    Re.value
+}
+
+// Qe = Qes * (Re + Rg) / Re, solved for Rg. Below Qes, Rg would be negative
+fn rg_for_qe(P: &Parameters, Qe: f64) -> f64 {
+    let Re: Resistance = P.Re.q();
+    let Rg: Resistance = Re * (Qe / P.Qes.v() - 1.0);
+    Rg.value
+}
+
+// m^4 / ((1 / s) * (m^5 / N)) = N * s / m
+fn rms_for_qms(P: &Parameters, Qms: f64) -> f64 {
+    let Sd: Area = P.Sd.q();
+    let ωs: Frequency = P.ωs.q();
+    let Cas: AcousticCompliance = P.Cas.q();
+    let Rms: MechanicalResistance = Sd * Sd / (ωs * Cas * Qms);
+    Rms.value
+}
+
+// Qts = Qes * Qms / (Qes + Qms), solved for Qes with Qms held
+fn bl_for_qts(P: &Parameters, Qts: f64) -> f64 {
+    let Qms = P.Qms.v();
+    bl_for_qes(P, Qts * Qms / (Qms - Qts))
+}
+
+// Qts = Qes * Qms / (Qes + Qms), solved for Qms with Qes held
+fn rms_for_qts(P: &Parameters, Qts: f64) -> f64 {
+    let Qes = P.Qes.v();
+    rms_for_qms(P, Qts * Qes / (Qes - Qts))
+}
+
+// m^3 / ((kg / m^3) * (m/s)^2) = m^5 / N
+fn cab_for_vb(P: &Parameters, Vb: f64) -> f64 {
+    let ρ0: MassDensity = P.ρ0.q();
+    let c: Velocity = P.c.q();
+    let Cab: AcousticCompliance = si::<dim::Volume>(Vb) / (ρ0 * c * c);
+    Cab.value
+}
+
+// m^3 / ((kg / m^3) * (m/s)^2) / m^4 = m / N
+fn cmp_for_vap(P: &Parameters, Vap: f64) -> f64 {
+    let ρ0: MassDensity = P.ρ0.q();
+    let c: Velocity = P.c.q();
+    let Sp: Area = P.Sp.q();
+    let Cmp: MechanicalCompliance = si::<dim::Volume>(Vap) / (ρ0 * c * c * Sp * Sp);
+    Cmp.value
+}
+
+// m^4 / ((m^5 / N) * Hz^2) = kg
+fn mmp_for_fp(P: &Parameters, Fp: f64) -> f64 {
+    let Fp: Frequency = si(Fp);
+    let Sp: Area = P.Sp.q();
+    let Cap: AcousticCompliance = P.Cap.q();
+    let Mmp: Mass = Sp * Sp / (Cap * (PI2 * Fp) * (PI2 * Fp));
+    Mmp.value
+}
+
+// 1 / ((kg / m^4) * Hz^2 * m^4) = m / N
+fn cmp_for_fp(P: &Parameters, Fp: f64) -> f64 {
+    let Fp: Frequency = si(Fp);
+    let Sp: Area = P.Sp.q();
+    let Map: AcousticMass = P.Map.q();
+    let Cmp: MechanicalCompliance = 1.0 / (Map * (PI2 * Fp) * (PI2 * Fp) * Sp * Sp);
+    Cmp.value
+}
+
+// m^4 / ((1 / s) * (m^5 / N)) = N * s / m
+fn rmp_for_qmp(P: &Parameters, Qmp: f64) -> f64 {
+    let Sp: Area = P.Sp.q();
+    let ωp: Frequency = P.ωp.q();
+    let Cap: AcousticCompliance = P.Cap.q();
+    let Rmp: MechanicalResistance = Sp * Sp / (ωp * Cap * Qmp);
+    Rmp.value
+}
+
+// ωb^2 * Map = 1 / Cab + 1 / Cap, solved for Cab
+fn cab_for_fb(P: &Parameters, Fb: f64) -> f64 {
+    let ωb: Frequency = PI2 * si::<dim::Frequency>(Fb);
+    let Map: AcousticMass = P.Map.q();
+    let Cap: AcousticCompliance = P.Cap.q();
+    let Cab: AcousticCompliance = 1.0 / (ωb * ωb * Map - 1.0 / Cap);
+    Cab.value
+}
+
+// (Cab + Cap) / (Cab * Cap * ωb^2) * m^4 = kg
+fn mmp_for_fb(P: &Parameters, Fb: f64) -> f64 {
+    let ωb: Frequency = PI2 * si::<dim::Frequency>(Fb);
+    let Sp: Area = P.Sp.q();
+    let Cab: AcousticCompliance = P.Cab.q();
+    let Cap: AcousticCompliance = P.Cap.q();
+    let Mmp: Mass = (Cab + Cap) * Sp * Sp / (Cab * Cap * ωb * ωb);
+    Mmp.value
+}
+
+// Fc = Fs * sqrt(1 + Cas / Cab)
+fn cab_for_fc(P: &Parameters, Fc: f64) -> f64 {
+    P.Cas.v() / ((Fc / P.Fs.v()).powi(2) - 1.0)
+}
+
+// Qtc = Qs * sqrt(1 + Cas / Cab)
+fn cab_for_qtc(P: &Parameters, Qtc: f64) -> f64 {
+    P.Cas.v() / ((Qtc / P.Qs.v()).powi(2) - 1.0)
+}
+
+// (m^5 / N) / 1 = m^5 / N
+fn cab_for_α(P: &Parameters, α: f64) -> f64 {
+    P.Cas.v() / α
+}
+
+// 1 / QB = 1 / QL + 1 / QA + 1 / QP, solved for QL. Absorption and port losses alone may already
+// be too much for QB, in which case no leakage can reach it
+fn ql_for_qb(P: &Parameters, QB: f64) -> f64 {
+    let leakage = 1.0 / QB - 1.0 / P.QA.v() - 1.0 / P.QP.v();
+    if leakage > 0.0 { 1.0 / leakage } else { f64::NAN }
+}
+
+// Γ = 1 / (h * QB), so fitting Γ to a measurement sets the leakage
+fn ql_for_Γ(P: &Parameters, Γ: f64) -> f64 {
+    ql_for_qb(P, 1.0 / (P.h.v() * Γ))
+}
+
+// 1 / ((kg / m^4) * Hz^2) = m^5 / N
+fn cab_for_fv(P: &Parameters, Fv: f64) -> f64 {
+    let ωv: Frequency = PI2 * si::<dim::Frequency>(Fv);
+    let Mav: AcousticMass = P.Mav.q();
+    let Cab: AcousticCompliance = 1.0 / (ωv * ωv * Mav);
+    Cab.value
+}
+
+// Acoustic mass the ports need to tune the box to Fv
+fn mav_for_fv(P: &Parameters, Fv: f64) -> AcousticMass {
+    mass_for_tuning(Fv, P.Cab.q())
+}
+
+// Length of `N` ports of area `S` with acoustic mass `Ma`, the inverse of `port_mass`
+// (kg / m^4) * m^2 / (kg / m^3) = m
+fn port_length(P: &Parameters, Ma: AcousticMass, N: Ratio, S: Area) -> f64 {
+    let ρ0: MassDensity = P.ρ0.q();
+    let Lve: Length = Ma * N * S / ρ0;
+    let L: Length = Lve - end_correction(P.Nf.v()) * (S / PI).sqrt();
+    L.value
+}
+
+// Ma = ρ0 * (L + k * D / 2) / (N * π * D^2 / 4) is a quadratic in the diameter D of `N` round
+// ports of length `L`
+fn port_diameter(P: &Parameters, Ma: AcousticMass, N: Ratio, L: Length) -> f64 {
+    let a = Ma.value * N.value * PI / 4.0;
+    let b = -P.ρ0.v() * end_correction(P.Nf.v()) / 2.0;
+    let c = -P.ρ0.v() * L.value;
+    (-b + (b * b - 4.0 * a * c).sqrt()) / (2.0 * a)
+}
+
+fn lv_for_fv(P: &Parameters, Fv: f64) -> f64 {
+    port_length(P, mav_for_fv(P, Fv), P.Nv.q(), P.Sv.q())
+}
+
+// Only round ports depend on Dv
+fn dv_for_fv(P: &Parameters, Fv: f64) -> f64 {
+    if P.Wv.v() > 0.0 && P.Hv.v() > 0.0 {
+        return f64::NAN;
+    }
+    port_diameter(P, mav_for_fv(P, Fv), P.Nv.q(), P.Lv.q())
+}
+
+// Acoustic mass that tunes a chamber of compliance `Ca` to `F`
+// 1 / (Hz^2 * (m^5 / N)) = kg / m^4
+fn mass_for_tuning(F: f64, Ca: AcousticCompliance) -> AcousticMass {
+    let ω: Frequency = PI2 * si::<dim::Frequency>(F);
+    1.0 / (ω * ω * Ca)
+}
+
+// Volume of a chamber that tunes to `F` with port mass `Ma`
+// (kg / m^3) * (m/s)^2 / (Hz^2 * (kg / m^4)) = m^3
+fn volume_for_tuning(P: &Parameters, F: f64, Ma: AcousticMass) -> f64 {
+    let ω: Frequency = PI2 * si::<dim::Frequency>(F);
+    let ρ0: MassDensity = P.ρ0.q();
+    let c: Velocity = P.c.q();
+    let V: Volume = ρ0 * c * c / (ω * ω * Ma);
+    V.value
+}
+
+fn lvr_for_fr(P: &Parameters, Fr: f64) -> f64 {
+    port_length(P, mass_for_tuning(Fr, P.Car.q()), P.Nvr.q(), P.Svr.q())
+}
+
+fn dvr_for_fr(P: &Parameters, Fr: f64) -> f64 {
+    port_diameter(P, mass_for_tuning(Fr, P.Car.q()), P.Nvr.q(), P.Lvr.q())
+}
+
+fn vr_for_fr(P: &Parameters, Fr: f64) -> f64 {
+    volume_for_tuning(P, Fr, P.Mar.q())
+}
+
+fn lvf_for_ff(P: &Parameters, Ff: f64) -> f64 {
+    port_length(P, mass_for_tuning(Ff, P.Caf.q()), P.Nvf.q(), P.Svf.q())
+}
+
+fn dvf_for_ff(P: &Parameters, Ff: f64) -> f64 {
+    port_diameter(P, mass_for_tuning(Ff, P.Caf.q()), P.Nvf.q(), P.Lvf.q())
+}
+
+fn vf_for_ff(P: &Parameters, Ff: f64) -> f64 {
+    volume_for_tuning(P, Ff, P.Maf.q())
+}
+
+// sqrt(W * ohm) = V
+fn vin_for_pin(P: &Parameters, Pin: f64) -> f64 {
+    let Re: Resistance = P.Re.q();
+    let Rg: Resistance = P.Rg.q();
+    let Vin: Voltage = (si::<dim::Power>(Pin) * (Re + Rg)).sqrt();
+    Vin.value
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use parameters::builtin_defaults;
+
+    fn nearly_equal(a: f64, b: f64) -> bool {
+        ((a - b) / b).abs() < 1e-9
+    }
+
+    #[test]
+    fn inversions_round_trip() {
+        for inversion in INVERSIONS.iter() {
+            let P = builtin_defaults().unwrap();
+            let target = P.get(inversion.target).unwrap();
+            let input = P.get(inversion.input).unwrap();
+            let requested = target.v() * 1.05;
+
+            // Bypass `solve` so the input's range can't reject the requested value
+            let value = (inversion.solve)(&P, requested);
+            P.set(&input, value);
+            assert!(nearly_equal(target.v(), requested),
+                "{} via {}: {} != {}", inversion.target, inversion.input, target.v(), requested);
+        }
+    }
+
+    #[test]
+    fn solve_rejects_unknown_input() {
+        let P = builtin_defaults().unwrap();
+        let result = P.solve(&P.Fs, "Xmax", 50.0);
+        assert_eq!(result, Err(SolveError::NoInversion {
+            target: "Fs".to_string(),
+            input: "Xmax".to_string(),
+        }));
+    }
+}
@@ -0,0 +1,124 @@
+//! Preset files found in the preset directories, and managing them from the GUI
+
+use find_folder;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const PRESET_EXTENSION: &str = "bass";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Preset {
+    /// File name without the extension
+    pub name: String,
+    pub path: PathBuf,
+}
+
+impl Preset {
+    fn from_path(path: PathBuf) -> Option<Preset> {
+        if path.extension().map_or(true, |ext| ext != PRESET_EXTENSION) {
+            return None;
+        }
+        let name = path.file_stem()?.to_string_lossy().into_owned();
+        Some(Preset { name, path })
+    }
+
+    fn sibling(&self, name: &str) -> PathBuf {
+        self.path.with_file_name(format!("{}.{}", name, PRESET_EXTENSION))
+    }
+}
+
+/// Directories searched for presets: the bundled `resources/presets`, and `~/.bass-calc/presets`
+/// if it exists
+pub fn preset_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![];
+    if let Ok(resources) = find_folder::Search::KidsThenParents(3, 5).for_folder("resources") {
+        dirs.push(resources.join("presets"));
+    }
+    if let Some(home) = env::var_os("HOME") {
+        let user_dir = Path::new(&home).join(".bass-calc").join("presets");
+        if user_dir.is_dir() {
+            dirs.push(user_dir);
+        }
+    }
+    dirs
+}
+
+/// Every preset file in `dirs`, sorted by name. Directories that can't be read are skipped
+pub fn scan(dirs: &[PathBuf]) -> Vec<Preset> {
+    let mut presets = dirs.iter()
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flat_map(|entries| entries.filter_map(|entry| entry.ok()))
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .filter_map(Preset::from_path)
+        .collect::<Vec<Preset>>();
+
+    presets.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()).then(a.path.cmp(&b.path)));
+    presets
+}
+
+fn already_exists(path: &Path) -> io::Error {
+    io::Error::new(io::ErrorKind::AlreadyExists, format!("{} already exists", path.display()))
+}
+
+/// Copy a preset to `<name> copy.bass` next to it, numbering the copy if that name is taken
+pub fn duplicate(preset: &Preset) -> io::Result<Preset> {
+    let mut name = format!("{} copy", preset.name);
+    let mut number = 2;
+    while preset.sibling(&name).exists() {
+        name = format!("{} copy {}", preset.name, number);
+        number += 1;
+    }
+    let path = preset.sibling(&name);
+    fs::copy(&preset.path, &path)?;
+    Ok(Preset { name, path })
+}
+
+/// Rename a preset within its directory, without replacing an existing preset
+pub fn rename(preset: &Preset, name: &str) -> io::Result<Preset> {
+    let name = name.trim();
+    if name.is_empty() || name.contains(|c| c == '/' || c == '\\') {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("'{}' is not a valid preset name", name)));
+    }
+    let path = preset.sibling(name);
+    if path.exists() {
+        return Err(already_exists(&path));
+    }
+    fs::rename(&preset.path, &path)?;
+    Ok(Preset { name: name.to_string(), path })
+}
+
+pub fn delete(preset: &Preset) -> io::Result<()> {
+    fs::remove_file(&preset.path)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn manage_presets() {
+        let dir = env::temp_dir().join("bass-calc-library");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("Woofer.bass"), "Name, Default, Min, Max, Prec., Units\n").unwrap();
+        fs::write(dir.join("notes.txt"), "").unwrap();
+
+        let dirs = vec![dir.clone()];
+        let presets = scan(&dirs);
+        assert_eq!(presets.iter().map(|p| &*p.name).collect::<Vec<_>>(), vec!["Woofer"]);
+
+        let copy = duplicate(&presets[0]).unwrap();
+        let copy2 = duplicate(&presets[0]).unwrap();
+        assert_eq!((&*copy.name, &*copy2.name), ("Woofer copy", "Woofer copy 2"));
+
+        assert_eq!(rename(&copy, "Woofer").unwrap_err().kind(), io::ErrorKind::AlreadyExists);
+        let renamed = rename(&copy, "Sub").unwrap();
+        delete(&copy2).unwrap();
+
+        assert_eq!(scan(&dirs), vec![renamed, presets[0].clone()]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,6 +1,16 @@
 
 pub mod defaults;
+pub mod graph;
+pub mod inversions;
+pub mod library;
 pub mod params;
+pub mod parse;
+pub mod units;
 
-pub use self::defaults::{builtin_defaults, file_defaults};
+pub use self::defaults::{builtin_defaults, defaults_path, file_defaults, load_file, load_lenient, save_file};
+pub use self::defaults::{LoadError, LoadMode, LoadProblem};
+pub use self::graph::GraphError;
+pub use self::inversions::{Inversion, SolveError};
+pub use self::library::Preset;
+pub use self::parse::UnitError;
 pub use self::params::{Param, Parameters, set_children};
\ No newline at end of file
@@ -2,19 +2,61 @@
 use std::cell::{Cell, Ref, RefCell};
 use std::rc::Rc;
 use std::f64::consts::PI;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use uom::si::Dimension;
+#[cfg(test)]
+use parameters::graph;
+use parameters::parse::{parse_quantity, UnitError};
+use parameters::units::*;
 
 const PI2: f64 = 2.0 * PI;
 
+/// A `Param` of dimension `D`, so that reading it as a quantity is checked at compile time
+///
+/// It dereferences to the `Param`, and `clone` gives the `Param` for child lists and groups
+pub struct TypedParam<D: Dimension + ?Sized> {
+    param: Param,
+    dimension: PhantomData<D>,
+}
+
+impl<D: Dimension + ?Sized> TypedParam<D> {
+    fn new(param: Param) -> TypedParam<D> {
+        TypedParam { param, dimension: PhantomData }
+    }
+
+    /// Value as a typed quantity
+    pub fn q(&self) -> Q<D> {
+        si(self.param.v())
+    }
+}
+
+impl<D: Dimension + ?Sized> Deref for TypedParam<D> {
+    type Target = Param;
+
+    fn deref(&self) -> &Param {
+        &self.param
+    }
+}
+
+impl<D: Dimension + ?Sized> DerefMut for TypedParam<D> {
+    fn deref_mut(&mut self) -> &mut Param {
+        &mut self.param
+    }
+}
+
 /// Parameter that depends on one or more children
+///
+/// The value, min and max are stored in SI base units. `unit` is only used for display and entry
 pub struct ParamPrivate {
     pub name: String,
-    pub unit: String,
+    pub unit: DisplayUnit,
     value: Cell<f64>,
     display: RefCell<String>,
-    pub min: f64,
-    pub max: f64,
-    pub update_fn: Option<fn(&Parameters) -> f64>,
+    min: Cell<f64>,
+    max: Cell<f64>,
+    pub update_fn: Option<Box<Fn(&Parameters) -> f64>>,
     precision: Cell<usize>,
     children: RefCell<Vec<Param>>,
     parents: RefCell<Vec<Param>>,
@@ -22,54 +64,109 @@ pub struct ParamPrivate {
 
 impl ParamPrivate {
     fn update(&self, params: &Parameters) {
-        if let Some(update_fn) = self.update_fn {
+        if let Some(ref update_fn) = self.update_fn {
             self.set(update_fn(params))
         }
     }
 
+    /// Value in SI base units. Tests trace reads to check the child lists, see `graph`
     pub fn v(&self) -> f64 {
+        #[cfg(test)]
+        graph::record_read(&self.name);
         self.value.get()
     }
-    /*
-    pub fn q<N>(&self) -> Quantity {
-        self.unit
+
+    /// Value in the parameter's display unit
+    pub fn display_value(&self) -> f64 {
+        self.v() / self.unit.scale
+    }
+
+    /// Convert a value in the parameter's display unit to SI base units
+    pub fn from_display(&self, value: f64) -> f64 {
+        value * self.unit.scale
+    }
+
+    /// Lower end of the slider range in SI base units
+    pub fn min(&self) -> f64 {
+        self.min.get()
+    }
+
+    /// Upper end of the slider range in SI base units
+    pub fn max(&self) -> f64 {
+        self.max.get()
+    }
+
+    /// Set the slider range in SI base units
+    pub fn set_range(&self, min: f64, max: f64) {
+        self.min.set(min);
+        self.max.set(max);
+    }
+
+    pub fn in_range(&self, value: f64) -> bool {
+        value >= self.min() && value <= self.max()
     }
-    */
 
     pub fn to_percent(&self) -> f64 {
-        (self.v() - self.min) / (self.max - self.min)
+        (self.v() - self.min()) / (self.max() - self.min())
+    }
+
+    pub fn from_percent(&self, percent: f64) -> f64 {
+        self.min() + percent*(self.max() - self.min())
     }
 
     pub fn set_percent(&self, percent: f64) {
-        let new_value = self.min + percent*(self.max - self.min);
+        let new_value = self.from_percent(percent);
         self.set(new_value);
-        let new_display = format!("{:.*}", self.precision(), new_value);
-        let _ = self.display.replace(new_display);
     }
 
+    /// Set the value in SI base units, and reformat the display text
     pub fn set(&self, new_value: f64) {
-        self.value.set(new_value)
+        self.value.set(new_value);
+        self.format_display();
     }
 
-    pub fn set_display(&self, display: String) {
-        if let Ok(val) = display.trim().parse::<f64>() {
-            self.set(val);
-        } else {
-            println!("Bad intermediate display");
+    fn format_display(&self) {
+        let new_display = format!("{:.*}", self.precision(), self.display_value());
+        let _ = self.display.replace(new_display);
+    }
+
+    /// Set the value from text such as `12 mm` or `1.2 cm`. The text is kept as typed
+    pub fn set_display(&self, display: String) -> Result<(), UnitError> {
+        let result = self.edit_display(display);
+        if let Ok(new_value) = result {
+            self.value.set(new_value);
         }
+        result.map(|_| ())
+    }
+
+    /// Replace the display text and parse it to SI base units, without changing the value
+    pub fn edit_display(&self, display: String) -> Result<f64, UnitError> {
+        let result = parse_quantity(&display, &self.unit);
         let _ = self.display.replace(display);
+        result
     }
 
     pub fn display(&self) -> Ref<String> {
         self.display.borrow()
     }
 
+    /// Parameters read by this parameter's update fn
+    pub fn children(&self) -> Ref<Vec<Param>> {
+        self.children.borrow()
+    }
+
+    /// Parameters whose update fns read this parameter
+    pub fn parents(&self) -> Ref<Vec<Param>> {
+        self.parents.borrow()
+    }
+
     pub fn precision(&self) -> usize {
         self.precision.get()
     }
 
     pub fn set_precision(&self, precision: usize) {
-        self.precision.set(precision)
+        self.precision.set(precision);
+        self.format_display();
     }
 }
 
@@ -79,70 +176,134 @@ pub type Param = Rc<ParamPrivate>;
 pub struct Parameters {
     pub param_map: HashMap<String, Param>,
 
-    pub driver: [Param; 23],
-    pub passive: [Param; 12],
-    pub enclosure: [Param; 8],
+    pub driver: [Param; 25],
+    pub passive: [Param; 13],
+    pub enclosure: [Param; 15],
+    pub vent: [Param; 10],
+    pub bandpass: [Param; 16],
+    pub voice_coil: [Param; 7],
+    pub drive: [Param; 5],
     pub constant: [Param; 3],
 
     // Environmental parameters
-    pub ρ0: Param,
-    pub c: Param,
-    pub t: Param,
+    pub ρ0: TypedParam<dim::MassDensity>,
+    pub c: TypedParam<dim::Velocity>,
+    pub t: TypedParam<dim::Time>,
 
     // Driver low level parameters
-    pub Xmax: Param,
-    pub Vd: Param,
-    pub Sd: Param,
-    pub Bl: Param,
-    pub Re: Param,
-    pub Mmd: Param,
-    pub Mms: Param,
-    pub Mas: Param,
-    pub Rms: Param,
-    pub Ras: Param,
-    pub Cms: Param,
-    pub Cas: Param,
-    pub Vas: Param,
-
-    pub Rg: Param,
+    pub Xmax: TypedParam<dim::Length>,
+    pub Vd: TypedParam<dim::Volume>,
+    pub Sd: TypedParam<dim::Area>,
+    pub Bl: TypedParam<dim::ForceFactor>,
+    pub Re: TypedParam<dim::Resistance>,
+    pub Mmd: TypedParam<dim::Mass>,
+    pub Mms: TypedParam<dim::Mass>,
+    pub Mas: TypedParam<dim::AcousticMass>,
+    pub Rms: TypedParam<dim::MechanicalResistance>,
+    pub Ras: TypedParam<dim::AcousticResistance>,
+    pub Cms: TypedParam<dim::MechanicalCompliance>,
+    pub Cas: TypedParam<dim::AcousticCompliance>,
+    pub Vas: TypedParam<dim::Volume>,
+
+    pub Rg: TypedParam<dim::Resistance>,
+    pub Pe: TypedParam<dim::Power>,
 
     // Driver mid level parameters
-    pub Ts: Param,
-    pub ωs: Param,
-    pub Fs: Param,
-    pub Qes: Param,
-    pub Qms: Param,
-    pub Qts: Param,
-    pub Qs: Param,
-    pub Cab: Param,
-    pub Vb: Param,
+    pub Ts: TypedParam<dim::Time>,
+    pub ωs: TypedParam<dim::Frequency>,
+    pub Fs: TypedParam<dim::Frequency>,
+    pub Qes: TypedParam<dim::Ratio>,
+    pub Qe: TypedParam<dim::Ratio>,
+    pub Qms: TypedParam<dim::Ratio>,
+    pub Qts: TypedParam<dim::Ratio>,
+    pub Qs: TypedParam<dim::Ratio>,
+    pub Cab: TypedParam<dim::AcousticCompliance>,
+    pub Vb: TypedParam<dim::Volume>,
 
     // Passive radiator low level parameters
-    pub Vap: Param,
-    pub Cmp: Param,
-    pub Cap: Param,
-    pub Rmp: Param,
-    pub Rap: Param,
-    pub Mmp: Param,
-    pub Map: Param,
-    pub Sp: Param,
+    pub Vap: TypedParam<dim::Volume>,
+    pub Cmp: TypedParam<dim::MechanicalCompliance>,
+    pub Cap: TypedParam<dim::AcousticCompliance>,
+    pub Rmp: TypedParam<dim::MechanicalResistance>,
+    pub Rap: TypedParam<dim::AcousticResistance>,
+    pub Mmp: TypedParam<dim::Mass>,
+    pub Map: TypedParam<dim::AcousticMass>,
+    pub Sp: TypedParam<dim::Area>,
+    pub Xmaxp: TypedParam<dim::Length>,
 
     // Passive radiator mid level parameters
-    pub Qmp: Param,
-    pub ωp: Param,
-    pub Fp: Param,
-    pub Tp: Param,
+    pub Qmp: TypedParam<dim::Ratio>,
+    pub ωp: TypedParam<dim::Frequency>,
+    pub Fp: TypedParam<dim::Frequency>,
+    pub Tp: TypedParam<dim::Time>,
 
     // Enclosure parameters
-    pub ωb: Param,
-    pub Fb: Param,
-    pub Tb: Param,
-
-    pub α: Param,
-    pub δ: Param,
-    pub y: Param,
-    pub h: Param,
-    pub η0: Param,
+    pub ωb: TypedParam<dim::Frequency>,
+    pub Fb: TypedParam<dim::Frequency>,
+    pub Tb: TypedParam<dim::Time>,
+
+    // Sealed box parameters
+    pub Fc: TypedParam<dim::Frequency>,
+    pub Qtc: TypedParam<dim::Ratio>,
+
+    // Box loss parameters
+    pub QL: TypedParam<dim::Ratio>,
+    pub QA: TypedParam<dim::Ratio>,
+    pub QP: TypedParam<dim::Ratio>,
+    pub QB: TypedParam<dim::Ratio>,
+    pub Γ: TypedParam<dim::Ratio>,
+
+    // Vent parameters
+    pub Nv: TypedParam<dim::Ratio>,
+    pub Dv: TypedParam<dim::Length>,
+    pub Wv: TypedParam<dim::Length>,
+    pub Hv: TypedParam<dim::Length>,
+    pub Lv: TypedParam<dim::Length>,
+    pub Nf: TypedParam<dim::Ratio>,
+    pub Sv: TypedParam<dim::Area>,
+    pub Lve: TypedParam<dim::Length>,
+    pub Mav: TypedParam<dim::AcousticMass>,
+    pub Fv: TypedParam<dim::Frequency>,
+
+    // Bandpass parameters
+    pub Vr: TypedParam<dim::Volume>,
+    pub Vf: TypedParam<dim::Volume>,
+    pub Nvr: TypedParam<dim::Ratio>,
+    pub Dvr: TypedParam<dim::Length>,
+    pub Lvr: TypedParam<dim::Length>,
+    pub Svr: TypedParam<dim::Area>,
+    pub Nvf: TypedParam<dim::Ratio>,
+    pub Dvf: TypedParam<dim::Length>,
+    pub Lvf: TypedParam<dim::Length>,
+    pub Svf: TypedParam<dim::Area>,
+    pub Car: TypedParam<dim::AcousticCompliance>,
+    pub Caf: TypedParam<dim::AcousticCompliance>,
+    pub Mar: TypedParam<dim::AcousticMass>,
+    pub Maf: TypedParam<dim::AcousticMass>,
+    pub Fr: TypedParam<dim::Frequency>,
+    pub Ff: TypedParam<dim::Frequency>,
+
+    // Voice coil parameters
+    pub Le: TypedParam<dim::Inductance>,
+    pub L2: TypedParam<dim::Inductance>,
+    pub R2: TypedParam<dim::Resistance>,
+    pub Krm: TypedParam<dim::Resistance>,
+    pub Erm: TypedParam<dim::Ratio>,
+    pub Kxm: TypedParam<dim::Resistance>,
+    pub Exm: TypedParam<dim::Ratio>,
+
+    // Drive parameters
+    pub Vin: TypedParam<dim::Voltage>,
+    pub Pin: TypedParam<dim::Power>,
+    pub r: TypedParam<dim::Length>,
+    pub Ftb: TypedParam<dim::Frequency>,
+    pub Ntb: TypedParam<dim::Ratio>,
+
+    pub α: TypedParam<dim::Ratio>,
+    pub δ: TypedParam<dim::Ratio>,
+    pub y: TypedParam<dim::Ratio>,
+    pub h: TypedParam<dim::Ratio>,
+    pub η0: TypedParam<dim::Ratio>,
 }
 
 impl Parameters {
@@ -156,29 +317,111 @@ impl Parameters {
         }
     }
 
+    /// Set `param` to `value` and recalculate everything that depends on it
+    pub fn set(&self, param: &Param, value: f64) {
+        param.set(value);
+        self.propagate(param);
+    }
+
+    /// Set `param` from a slider position and recalculate everything that depends on it
+    pub fn set_percent(&self, param: &Param, percent: f64) {
+        param.set_percent(percent);
+        self.propagate(param);
+    }
+
+    /// Set `param` from text entry and recalculate everything that depends on it
+    pub fn set_display(&self, param: &Param, display: String) -> Result<(), UnitError> {
+        param.set_display(display)?;
+        self.propagate(param);
+        Ok(())
+    }
+
+    /// Recalculate every parameter that depends on `param`, directly or through other parameters.
+    /// `param` itself is left untouched
+    pub fn propagate(&self, param: &Param) {
+        let order = topological_order(&[param.clone()]);
+        for dependent in order.iter().skip(1) {
+            dependent.update(self);
+        }
+    }
+
+    /// Recalculate every derived parameter from the current leaf values
+    pub fn update_all(&self) {
+        let roots = self.param_map.values().cloned().collect::<Vec<Param>>();
+        for param in topological_order(&roots) {
+            param.update(self);
+        }
+    }
+
+    /// Bit patterns of every parameter's value, in the same order each time, to tell whether any
+    /// has changed. NaN values compare equal to themselves
+    pub fn value_bits(&self) -> Vec<u64> {
+        self.param_map.values().map(|param| param.value.get().to_bits()).collect()
+    }
+
+    /// Copy the value, range and precision of every parameter from `defaults`, such as a fresh
+    /// `builtin_defaults`, so nothing is left over from an earlier preset
+    pub fn reset(&self, defaults: &Parameters) {
+        for (name, param) in self.param_map.iter() {
+            if let Some(default) = defaults.get(name) {
+                param.set_range(default.min(), default.max());
+                param.set_precision(default.precision());
+                param.set(default.value.get());
+            }
+        }
+        self.update_all();
+    }
+
+}
+
+/// Orders `roots` and all of their dependents so that every parameter comes after the parameters
+/// its update fn reads. This is a reverse post-order depth first search over the `parents` links
+fn topological_order(roots: &[Param]) -> Vec<Param> {
+
+    fn visit(param: &Param, visited: &mut HashSet<String>, order: &mut Vec<Param>) {
+        if visited.insert(param.name.clone()) {
+            for parent in param.parents.borrow().iter() {
+                visit(parent, visited, order);
+            }
+            order.push(param.clone());
+        }
+    }
+
+    let mut visited = HashSet::new();
+    let mut order = vec![];
+    for root in roots {
+        visit(root, &mut visited, &mut order);
+    }
+    order.reverse();
+    order
 }
 
-pub fn param_simple(name: &str, unit: &str, value: f64, min: f64, max: f64, precision: usize) -> Param {
+/// Leaf parameter. `value`, `min` and `max` are given in `unit`
+pub fn param_simple<D: Dimension + ?Sized>(name: &str, unit: Unit<D>, value: f64, min: f64, max: f64,
+        precision: usize) -> TypedParam<D> {
 
-    make_param(name, unit, value, min, max, precision, None)
+    TypedParam::new(make_param(name, unit.display(), value, min, max, precision, None))
 }
 
-pub fn param(name: &str, unit: &str, value: f64, min: f64, max: f64, precision: usize,
-        update: fn(&Parameters) -> f64) -> Param {
+/// Parameter calculated by `update`, which must return a quantity of the same dimension as `unit`
+pub fn param<D: Dimension + ?Sized + 'static>(name: &str, unit: Unit<D>, value: f64, min: f64, max: f64,
+        precision: usize, update: fn(&Parameters) -> Q<D>) -> TypedParam<D> {
 
-    make_param(name, unit, value, min, max, precision, Some(update))
+    let update_si = Box::new(move |P: &Parameters| update(P).value);
+    TypedParam::new(make_param(name, unit.display(), value, min, max, precision, Some(update_si)))
 }
 
-pub fn make_param(name: &str, unit: &str, value: f64, min: f64, max: f64, precision: usize,
-         update: Option<fn(&Parameters) -> f64>) -> Param {
+pub fn make_param(name: &str, unit: DisplayUnit, value: f64, min: f64, max: f64, precision: usize,
+         update: Option<Box<Fn(&Parameters) -> f64>>) -> Param {
 
+    let scale = unit.scale;
     Rc::new(ParamPrivate {
         name: name.to_string(),
-        unit: unit.to_string(),
-        value: Cell::new(value),
-        display: RefCell::new(value.to_string()),
-        min,
-        max,
+        unit,
+        value: Cell::new(value * scale),
+        display: RefCell::new(format!("{:.*}", precision, value)),
+        min: Cell::new(min * scale),
+        max: Cell::new(max * scale),
         precision: Cell::new(precision),
         update_fn: update,
         children: RefCell::new(vec![]),
@@ -196,145 +439,411 @@ pub fn set_children(param_ref: &mut Param, children: Vec<Param>) {
     }
 }
 
-// 10000 * cm^2 * mm = L
-pub fn vd_update(P: &Parameters) -> f64 {
-    10000.0 * P.Sd.v() * P.Xmax.v()
+// m^2 * m = m^3
+pub fn vd_update(P: &Parameters) -> Volume {
+    let Sd: Area = P.Sd.q();
+    let Xmax: Length = P.Xmax.q();
+    Sd * Xmax
+}
+
+// kg + kg = kg
+pub fn mms_update(P: &Parameters) -> Mass {
+    P.Mmd.q() + mms_air_load(P)
+}
+
+// Air mass loading both sides of the diaphragm, 8 ρ0 a^3 / 3 per side for a piston of radius a
+// in an infinite baffle
+// ((kg / m^3) / m) * m^4 = kg
+pub fn mms_air_load(P: &Parameters) -> Mass {
+    let Sd: Area = P.Sd.q();
+    let ρ0: MassDensity = P.ρ0.q();
+    let a = (Sd / PI).sqrt();
+    2.0 * ((8.0 * ρ0) / (3.0 * PI.powi(2) * a)) * Sd * Sd
+}
+
+// kg / m^4 = kg / m^4
+pub fn mas_update(P: &Parameters) -> AcousticMass {
+    let Sd: Area = P.Sd.q();
+    P.Mms.q() / (Sd * Sd)
+}
+
+// (N * s / m) / m^4 = (Pa * s) / m^3
+pub fn ras_update(P: &Parameters) -> AcousticResistance {
+    let Sd: Area = P.Sd.q();
+    P.Rms.q() / (Sd * Sd)
+}
+
+// (m / N) * m^4 = m^5 / N
+pub fn cas_update(P: &Parameters) -> AcousticCompliance {
+    let Sd: Area = P.Sd.q();
+    P.Cms.q() * Sd * Sd
+}
+
+// (kg / m^3) * (m/s)^2 * (m^5 / N) = m^3
+pub fn vas_update(P: &Parameters) -> Volume {
+    let ρ0: MassDensity = P.ρ0.q();
+    let c: Velocity = P.c.q();
+    ρ0 * c * c * P.Cas.q()
+}
+
+// 1 / (1 / s) = s
+pub fn ts_update(P: &Parameters) -> Time {
+    1.0 / P.ωs.q()
+}
+
+// Hz = 1 / s
+pub fn ωs_update(P: &Parameters) -> Frequency {
+    PI2 * P.Fs.q()
+}
+
+// 1 / sqrt((kg / m^4) * (m^5 / N)) = Hz
+pub fn fs_update(P: &Parameters) -> Frequency {
+    let Mas: AcousticMass = P.Mas.q();
+    let Cas: AcousticCompliance = P.Cas.q();
+    1.0 / (PI2 * (Mas * Cas).sqrt())
+}
+
+// (1 / s) * ohm * (kg / m^4) * m^4 / (tesla * m)^2 = 1
+pub fn qes_update(P: &Parameters) -> Ratio {
+    let Sd: Area = P.Sd.q();
+    let Bl: ForceFactor = P.Bl.q();
+    let ωs: Frequency = P.ωs.q();
+    let Re: Resistance = P.Re.q();
+    let Mas: AcousticMass = P.Mas.q();
+    (ωs * Re * Mas * Sd * Sd) / (Bl * Bl)
+}
+
+// 1 / ((1 / s) * (m^5 / N) * (Pa * s) / m^3) = 1
+pub fn qms_update(P: &Parameters) -> Ratio {
+    let ωs: Frequency = P.ωs.q();
+    let Cas: AcousticCompliance = P.Cas.q();
+    let Ras: AcousticResistance = P.Ras.q();
+    1.0 / (ωs * Cas * Ras)
+}
+
+// Rg in series with Re divides the electrical damping
+pub fn qe_update(P: &Parameters) -> Ratio {
+    let Qes: Ratio = P.Qes.q();
+    let Re: Resistance = P.Re.q();
+    let Rg: Resistance = P.Rg.q();
+    Qes * (Re + Rg) / Re
 }
 
-// g + 1000 * ((kg / m^3) / sqrt(cm^2)) * cm^4 = g
-pub fn mms_update(P: &Parameters) -> f64 {
-    let Sd = P.Sd.v();
-    P.Mmd.v() + 1000.0 * (2.0 * ((8.0 * P.ρ0.v()) / (3.0 * PI2 * ( Sd / PI ).sqrt()))) * Sd.powi(2)
+pub fn qts_update(P: &Parameters) -> Ratio {
+    let Qes: Ratio = P.Qes.q();
+    let Qms: Ratio = P.Qms.q();
+    (Qes * Qms) / (Qes + Qms)
 }
 
-// g / cm^4 = g / cm^4
-pub fn mas_update(P: &Parameters) -> f64 {
-    P.Mms.v() / P.Sd.v().powi(2)
+// Total Q of the driver as the system sees it, through Rg
+pub fn qs_update(P: &Parameters) -> Ratio {
+    let Qe: Ratio = P.Qe.q();
+    let Qms: Ratio = P.Qms.q();
+    (Qe * Qms) / (Qe + Qms)
 }
 
-// (N * s / m) / (100000000 * cm^4) = (Pa * s) / m^3
-pub fn ras_update(P: &Parameters) -> f64 {
-    P.Rms.v() / (100000000.0 * P.Sd.v().powi(2))
+// (kg / m^3) * (m/s)^2 * (m^5 / N) = m^3
+pub fn vb_update(P: &Parameters) -> Volume {
+    let ρ0: MassDensity = P.ρ0.q();
+    let c: Velocity = P.c.q();
+    ρ0 * c * c * P.Cab.q()
 }
 
-// 100000000 * (1 m / N) * cm^4 = m^5 / N
-pub fn cas_update(P: &Parameters) -> f64 {
-    100000000.0 * P.Cms.v() * P.Sd.v().powi(2)
+// (kg / m^3) * (m/s)^2 * (m^5 / N) = m^3
+pub fn vap_update(P: &Parameters) -> Volume {
+    let ρ0: MassDensity = P.ρ0.q();
+    let c: Velocity = P.c.q();
+    ρ0 * c * c * P.Cap.q()
 }
 
-// (kg / m^3) * (m/s)^2 * (m^5 / N) / 1000 = L
-pub fn vas_update(P: &Parameters) -> f64 {
-    P.ρ0.v() * P.c.v().powi(2) * P.Cas.v() / 1000.0
+// (m / N) * m^4 = m^5 / N
+pub fn cap_update(P: &Parameters) -> AcousticCompliance {
+    let Sp: Area = P.Sp.q();
+    P.Cmp.q() * Sp * Sp
 }
 
-// 1 / Hz = s
-pub fn ts_update(P: &Parameters) -> f64 {
-    1.0 / P.ωs.v()
+// (N * s / m) / m^4 = (Pa * s) / m^3
+pub fn rap_update(P: &Parameters) -> AcousticResistance {
+    let Sp: Area = P.Sp.q();
+    P.Rmp.q() / (Sp * Sp)
 }
 
-// Hz = Hz
-pub fn ωs_update(P: &Parameters) -> f64 {
-    P.Fs.v() * PI2
+// kg / m^4 = kg / m^4
+pub fn map_update(P: &Parameters) -> AcousticMass {
+    let Sp: Area = P.Sp.q();
+    P.Mmp.q() / (Sp * Sp)
 }
 
-// 1 / sqrt((g/cm^4) * (m^5 / N) / 100000) = Hz
-pub fn fs_update(P: &Parameters) -> f64 {
-    1.0 / ( PI2 * (P.Mas.v() * P.Cas.v() / 100000.0).sqrt())
+// 1 / ((1 / s) * (m^5 / N) * (Pa * s) / m^3) = 1
+pub fn qmp_update(P: &Parameters) -> Ratio {
+    let ωp: Frequency = P.ωp.q();
+    let Cap: AcousticCompliance = P.Cap.q();
+    let Rap: AcousticResistance = P.Rap.q();
+    1.0 / (ωp * Cap * Rap)
 }
 
-// 1000 * (Hz * Ohm * (g/cm^4) * cm^4) / (tesla * m)^2 = 1
-pub fn qes_update(P: &Parameters) -> f64 {
-    1000.0 * (P.ωs.v() * P.Re.v() * P.Mas.v() * P.Sd.v().powi(2)) / P.Bl.v().powi(2)
+// 1 / sqrt((kg / m^4) * (m^5 / N)) = Hz
+pub fn fp_update(P: &Parameters) -> Frequency {
+    let Map: AcousticMass = P.Map.q();
+    let Cap: AcousticCompliance = P.Cap.q();
+    1.0 / (PI2 * (Map * Cap).sqrt())
 }
 
-// 1 / (Hz * (m^5 / N) * (Pa * s) / m^3) = 1
-pub fn qms_update(P: &Parameters) -> f64 {
-    1.0 / (P.ωs.v() * P.Cas.v() * P.Ras.v())
+// 1 / (1 / s) = s
+pub fn tp_update(P: &Parameters) -> Time {
+    1.0 / P.ωp.q()
 }
 
-pub fn qts_update(P: &Parameters) -> f64 {
-    (P.Qes.v() * P.Qms.v()) / (P.Qes.v() + P.Qms.v())
+pub fn ωp_update(P: &Parameters) -> Frequency {
+    PI2 * P.Fp.q()
 }
 
-pub fn qs_update(P: &Parameters) -> f64 {
-    P.Qts.v()
+// Resonance of the passive radiator mass against the box and suspension compliances in series
+// sqrt(1 / ((m^5 / N) * (kg / m^4))) = Hz
+pub fn fb_update(P: &Parameters) -> Frequency {
+    let Cab: AcousticCompliance = P.Cab.q();
+    let Cap: AcousticCompliance = P.Cap.q();
+    let Map: AcousticMass = P.Map.q();
+    ((Cab + Cap) / (Cab * Cap * Map)).sqrt() / PI2
 }
 
-// (kg/m^3) * (m/s)^2 * (m^5 / N) / 1000 = L
-pub fn vb_update(P: &Parameters) -> f64 {
-    P.ρ0.v() * P.c.v().powi(2) * P.Cab.v() / 1000.0
+// 1 / (1 / s) = s
+pub fn tb_update(P: &Parameters) -> Time {
+    1.0 / P.ωb.q()
 }
 
-// (kg/m^3) * (m/s)^2 * (m^5 / N) / 1000 = L
-pub fn vap_update(P: &Parameters) -> f64 {
-    P.ρ0.v() * P.c.v().powi(2) * P.Cap.v()
+pub fn ωb_update(P: &Parameters) -> Frequency {
+    PI2 * P.Fb.q()
 }
 
-// 100000000 * (m/N) * cm^4 = m^5 / N
-pub fn cap_update(P: &Parameters) -> f64 {
-    100000000.0 * P.Cmp.v() * P.Sp.v().powi(2)
+// Hz * sqrt(1) = Hz
+pub fn fc_update(P: &Parameters) -> Frequency {
+    let Fs: Frequency = P.Fs.q();
+    let α: Ratio = P.α.q();
+    Fs * (1.0 + α.value).sqrt()
 }
 
-// ((N * s / m) / cm^4) / 100000000 = (Pa * s) / m^3
-pub fn rap_update(P: &Parameters) -> f64 {
-    (P.Rmp.v() / P.Sp.v().powi(2)) * 100000000.0
+pub fn qtc_update(P: &Parameters) -> Ratio {
+    let Qs: Ratio = P.Qs.q();
+    let α: Ratio = P.α.q();
+    Qs * (1.0 + α.value).sqrt()
 }
 
-// kg / cm^4 = kg / cm^4
-pub fn map_update(P: &Parameters) -> f64 {
-    P.Mmp.v() / P.Sp.v().powi(2)
+// Leakage, absorption and port or passive radiator losses act as resistances in parallel
+pub fn qb_update(P: &Parameters) -> Ratio {
+    let QL: Ratio = P.QL.q();
+    let QA: Ratio = P.QA.q();
+    let QP: Ratio = P.QP.q();
+    1.0 / (1.0 / QL + 1.0 / QA + 1.0 / QP)
 }
 
-// 1 / (Hz * (m^5 / N) * (Pa * s)/m^3) = 1
-pub fn qmp_update(P: &Parameters) -> f64 {
-    1.0 / (P.ωp.v() * P.Cap.v() * P.Rap.v())
+// Hurlburt lumps the box losses into a time constant τb, and Γ = τb / Ts. Losses with total Q of
+// QB at the box resonance give τb = 1 / (ωb * QB), so Γ = ωs / (ωb * QB). This is the passive
+// radiator box, resonant at Fb = h * Fs. Only the passive radiator functions read Γ, the vented
+// ones take QB at Fv directly
+pub fn Γ_update(P: &Parameters) -> Ratio {
+    let h: Ratio = P.h.q();
+    let QB: Ratio = P.QB.q();
+    1.0 / (h * QB)
 }
 
-// 10000 / sqrt((kg/cm^4) * (m^5/N)) = Hz
-pub fn fp_update(P: &Parameters) -> f64 {
-    10000.0 / ( PI2 * (P.Map.v() * P.Cap.v()).sqrt())
+// Cross section of one port: a slot if both slot dimensions are set, otherwise round. All three
+// are read before branching, for dependency validation
+pub fn sv_update(P: &Parameters) -> Area {
+    let Dv: Length = P.Dv.q();
+    let Wv: Length = P.Wv.q();
+    let Hv: Length = P.Hv.q();
+    if Wv.value > 0.0 && Hv.value > 0.0 {
+        Wv * Hv
+    } else {
+        PI / 4.0 * Dv * Dv
+    }
+}
+
+/// End correction of a port with `flanged` of its two ends flanged, in radii of a round port
+/// with the same area
+pub fn end_correction(flanged: f64) -> f64 {
+    0.849 * flanged + 0.613 * (2.0 - flanged)
+}
+
+// Length of air that moves with the port, including the air just beyond each end
+// m + sqrt(m^2) = m
+pub fn lve_update(P: &Parameters) -> Length {
+    let Lv: Length = P.Lv.q();
+    let Sv: Area = P.Sv.q();
+    Lv + end_correction(P.Nf.v()) * (Sv / PI).sqrt()
+}
+
+// Acoustic mass of all the ports in parallel
+// (kg / m^3) * m / m^2 = kg / m^4
+pub fn mav_update(P: &Parameters) -> AcousticMass {
+    let ρ0: MassDensity = P.ρ0.q();
+    let Lve: Length = P.Lve.q();
+    let Nv: Ratio = P.Nv.q();
+    let Sv: Area = P.Sv.q();
+    ρ0 * Lve / (Nv * Sv)
+}
+
+// Resonance of the port air mass against the box compliance
+// sqrt(1 / ((kg / m^4) * (m^5 / N))) = Hz
+pub fn fv_update(P: &Parameters) -> Frequency {
+    let Mav: AcousticMass = P.Mav.q();
+    let Cab: AcousticCompliance = P.Cab.q();
+    1.0 / (Mav * Cab).sqrt() / PI2
+}
+
+// m^3 / ((kg / m^3) * (m/s)^2) = m^5 / N
+pub fn car_update(P: &Parameters) -> AcousticCompliance {
+    let ρ0: MassDensity = P.ρ0.q();
+    let c: Velocity = P.c.q();
+    P.Vr.q() / (ρ0 * c * c)
+}
+
+// m^3 / ((kg / m^3) * (m/s)^2) = m^5 / N
+pub fn caf_update(P: &Parameters) -> AcousticCompliance {
+    let ρ0: MassDensity = P.ρ0.q();
+    let c: Velocity = P.c.q();
+    P.Vf.q() / (ρ0 * c * c)
+}
+
+// Bandpass ports are round
+// m^2 = m^2
+pub fn svr_update(P: &Parameters) -> Area {
+    let Dvr: Length = P.Dvr.q();
+    PI / 4.0 * Dvr * Dvr
 }
 
-// 1 / Hz = s
-pub fn tp_update(P: &Parameters) -> f64 {
-    1.0 / P.ωp.v()
+// m^2 = m^2
+pub fn svf_update(P: &Parameters) -> Area {
+    let Dvf: Length = P.Dvf.q();
+    PI / 4.0 * Dvf * Dvf
 }
 
-pub fn ωp_update(P: &Parameters) -> f64 {
-    P.Fp.v() * PI2
+// Acoustic mass of `N` ports in parallel, each of area `S` and length `L`. Their ends are
+// corrected like the vented box ports, with `Nf` of the two flanged
+// (kg / m^3) * m / m^2 = kg / m^4
+pub fn port_mass(P: &Parameters, N: Ratio, S: Area, L: Length) -> AcousticMass {
+    let ρ0: MassDensity = P.ρ0.q();
+    ρ0 * (L + end_correction(P.Nf.v()) * (S / PI).sqrt()) / (N * S)
 }
 
-// 10000 * sqrt(1 / (m^5/N * kg/cm^4)) = Hz
-pub fn fb_update(P: &Parameters) -> f64 {
-    10000.0 * ((1.0 + (P.Cab.v() / P.Cap.v())) / (PI2 * P.Cab.v() * P.Map.v())).sqrt()
+pub fn mar_update(P: &Parameters) -> AcousticMass {
+    port_mass(P, P.Nvr.q(), P.Svr.q(), P.Lvr.q())
 }
 
-// 1 / Hz = s
-pub fn tb_update(P: &Parameters) -> f64 {
-    1.0 / P.ωp.v()
+pub fn maf_update(P: &Parameters) -> AcousticMass {
+    port_mass(P, P.Nvf.q(), P.Svf.q(), P.Lvf.q())
 }
 
-pub fn ωb_update(P: &Parameters) -> f64 {
-    P.Fp.v() * PI2
+// Resonance of the rear port air mass against the rear chamber
+// sqrt(1 / ((kg / m^4) * (m^5 / N))) = Hz
+pub fn fr_update(P: &Parameters) -> Frequency {
+    let Mar: AcousticMass = P.Mar.q();
+    let Car: AcousticCompliance = P.Car.q();
+    1.0 / (Mar * Car).sqrt() / PI2
 }
 
-pub fn α_update(P: &Parameters) -> f64 {
-    P.Cas.v() / P.Cab.v()
+// Resonance of the front port air mass against the front chamber
+// sqrt(1 / ((kg / m^4) * (m^5 / N))) = Hz
+pub fn ff_update(P: &Parameters) -> Frequency {
+    let Maf: AcousticMass = P.Maf.q();
+    let Caf: AcousticCompliance = P.Caf.q();
+    1.0 / (Maf * Caf).sqrt() / PI2
 }
 
-pub fn δ_update(P: &Parameters) -> f64 {
-    P.Cap.v() / P.Cab.v()
+// Power the source delivers into Rg and the voice coil resistance
+// V^2 / ohm = W
+pub fn pin_update(P: &Parameters) -> Power {
+    let Vin: Voltage = P.Vin.q();
+    let Re: Resistance = P.Re.q();
+    let Rg: Resistance = P.Rg.q();
+    Vin * Vin / (Re + Rg)
 }
 
-pub fn y_update(P: &Parameters) -> f64 {
-    P.Fp.v() / P.Fs.v()
+pub fn α_update(P: &Parameters) -> Ratio {
+    P.Cas.q() / P.Cab.q()
 }
 
-pub fn h_update(P: &Parameters) -> f64 {
-    P.Fb.v() / P.Fs.v()
+pub fn δ_update(P: &Parameters) -> Ratio {
+    P.Cap.q() / P.Cab.q()
 }
 
-// (1000 / (m/s)^3) * (Hz^3 * L) = 1
-pub fn η0_update(P: &Parameters) -> f64 {
-    ((4.0 * PI.powi(2)) / P.c.v().powi(3)) * (P.Fs.v().powi(3) * P.Vas.v() / P.Qes.v())
+pub fn y_update(P: &Parameters) -> Ratio {
+    P.Fp.q() / P.Fs.q()
+}
+
+pub fn h_update(P: &Parameters) -> Ratio {
+    P.Fb.q() / P.Fs.q()
+}
+
+// (1 / (m/s)^3) * (Hz^3 * m^3) = 1. Relative to the power drawn from the source, including what
+// Rg dissipates
+pub fn η0_update(P: &Parameters) -> Ratio {
+    let c: Velocity = P.c.q();
+    let Fs: Frequency = P.Fs.q();
+    let Vas: Volume = P.Vas.q();
+    let Qe: Ratio = P.Qe.q();
+    ((4.0 * PI.powi(2)) / (c * c * c)) * (Fs * Fs * Fs * Vas / Qe)
+}
+#[cfg(test)]
+mod test {
+    use parameters::builtin_defaults;
+    use super::*;
+
+    #[test]
+    fn air_load_and_box_tuning() {
+        let P = builtin_defaults().unwrap();
+
+        // A 10 cm radius piston carries 16 ρ0 a^3 / 3 of air, 6.4 g at 1.2 kg/m^3
+        P.set(&P.ρ0, 1.2);
+        P.set(&P.Sd, PI * 0.1f64.powi(2));
+        assert!((mms_air_load(&P).value - 6.4e-3).abs() < 1e-12);
+        assert!((P.Mms.v() - P.Mmd.v() - 6.4e-3).abs() < 1e-12);
+
+        // 50 kg/m^4 against 1e-8 m^5/N in the box and in the suspension tunes to
+        // 1 / (2π sqrt(50 * 5e-9)) = 318.31 Hz
+        P.set(&P.Sp, 0.01);
+        P.set(&P.Cab, 1e-8);
+        P.set(&P.Cmp, 1e-4);
+        P.set(&P.Mmp, 5e-3);
+        assert!((P.Fb.v() - 318.3099).abs() < 1e-3);
+
+        // A 5 cm port 10 cm long with both ends free moves 10 + 1.226 * 2.5 = 13.065 cm of air,
+        // 79.847 kg/m^4, and tunes 10 L at 345 m/s to 67.313 Hz
+        P.set(&P.c, 345.0);
+        P.set(&P.Nf, 0.0);
+        P.set(&P.Vf, 0.01);
+        P.set(&P.Dvf, 0.05);
+        P.set(&P.Lvf, 0.1);
+        assert!((P.Maf.v() - 79.847).abs() < 1e-3);
+        assert!((P.Ff.v() - 67.313).abs() < 1e-3);
+    }
+
+    #[test]
+    fn propagate_dependents() {
+        let P = builtin_defaults().unwrap();
+        P.set(&P.Sd, P.Sd.from_display(20.0));
+
+        assert_eq!(P.Vd.v(), vd_update(&P).value);
+        assert_eq!(P.Mas.v(), mas_update(&P).value);
+        assert_eq!(P.Fs.v(), fs_update(&P).value);
+        assert_eq!(P.ωs.v(), ωs_update(&P).value);
+        assert_eq!(P.Qes.v(), qes_update(&P).value);
+        assert_eq!(P.Qts.v(), qts_update(&P).value);
+        assert_eq!(P.η0.v(), η0_update(&P).value);
+    }
+
+    #[test]
+    fn topological_order_inputs_first() {
+        let P = builtin_defaults().unwrap();
+        let order = topological_order(&[P.Sd.clone()]);
+        let position = |param: &Param| order.iter().position(|p| p.name == param.name).unwrap();
+
+        assert_eq!(position(&P.Sd), 0);
+        assert!(position(&P.Mms) < position(&P.Mas));
+        assert!(position(&P.Mas) < position(&P.Fs));
+        assert!(position(&P.Fs) < position(&P.ωs));
+        assert!(position(&P.ωs) < position(&P.Qes));
+        assert!(position(&P.Qes) < position(&P.Qts));
+    }
 }
@@ -0,0 +1,408 @@
+//! Parsing of quantities typed by the user or read from preset files, such as `12 mm`,
+//! `3.5 L`, `0.12 ft^3`, `kg / m**3` or `35Hz`
+//!
+//! Hz and rad/s have the same base dimensions, but a cycle is 2π radians. Units keep count of the
+//! cycles and radians in them, so that a frequency in Hz entered for a parameter in rad/s, or the
+//! other way round, is converted
+
+use parameters::units::DisplayUnit;
+use std::f64::consts::PI;
+use std::fmt;
+
+#[derive(Debug, PartialEq)]
+pub enum UnitError {
+    /// No number at the start of the text
+    BadNumber(String),
+    /// A unit name that isn't known
+    UnknownUnit(String),
+    /// The unit expression is malformed
+    Syntax(String),
+    /// The unit has a different dimension than the parameter
+    Incompatible { unit: String, expected: String },
+}
+
+impl fmt::Display for UnitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            UnitError::BadNumber(ref text) => write!(f, "'{}' is not a number", text),
+            UnitError::UnknownUnit(ref unit) => write!(f, "unknown unit '{}'", unit),
+            UnitError::Syntax(ref unit) => write!(f, "cannot read unit '{}'", unit),
+            UnitError::Incompatible { ref unit, ref expected } => {
+                if expected.is_empty() {
+                    write!(f, "'{}' is not unitless", unit)
+                } else {
+                    write!(f, "'{}' is not compatible with {}", unit, expected)
+                }
+            },
+        }
+    }
+}
+
+/// A unit expression reduced to its size in SI base units and its base dimensions
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ParsedUnit {
+    pub scale: f64,
+    pub dimension: [i32; 7],
+    /// Powers of cycles, as in Hz, and of radians, which base dimensions don't tell apart
+    pub cycles: i32,
+    pub radians: i32,
+}
+
+impl ParsedUnit {
+    fn one() -> ParsedUnit {
+        ParsedUnit { scale: 1.0, dimension: [0; 7], cycles: 0, radians: 0 }
+    }
+
+    fn mul(self, other: ParsedUnit) -> ParsedUnit {
+        let mut dimension = self.dimension;
+        for (d, o) in dimension.iter_mut().zip(other.dimension.iter()) {
+            *d += *o;
+        }
+        ParsedUnit {
+            scale: self.scale * other.scale,
+            dimension,
+            cycles: self.cycles + other.cycles,
+            radians: self.radians + other.radians,
+        }
+    }
+
+    fn powi(self, exp: i32) -> ParsedUnit {
+        let mut dimension = self.dimension;
+        for d in dimension.iter_mut() {
+            *d *= exp;
+        }
+        ParsedUnit {
+            scale: self.scale.powi(exp),
+            dimension,
+            cycles: self.cycles * exp,
+            radians: self.radians * exp,
+        }
+    }
+
+    fn has_angle(&self) -> bool {
+        self.cycles != 0 || self.radians != 0
+    }
+}
+
+const LENGTH: [i32; 7] = [1, 0, 0, 0, 0, 0, 0];
+const VOLUME: [i32; 7] = [3, 0, 0, 0, 0, 0, 0];
+const MASS: [i32; 7] = [0, 1, 0, 0, 0, 0, 0];
+const TIME: [i32; 7] = [0, 0, 1, 0, 0, 0, 0];
+const CURRENT: [i32; 7] = [0, 0, 0, 1, 0, 0, 0];
+const FREQUENCY: [i32; 7] = [0, 0, -1, 0, 0, 0, 0];
+const FORCE: [i32; 7] = [1, 1, -2, 0, 0, 0, 0];
+const PRESSURE: [i32; 7] = [-1, 1, -2, 0, 0, 0, 0];
+const ENERGY: [i32; 7] = [2, 1, -2, 0, 0, 0, 0];
+const POWER: [i32; 7] = [2, 1, -3, 0, 0, 0, 0];
+const VOLTAGE: [i32; 7] = [2, 1, -3, -1, 0, 0, 0];
+const RESISTANCE: [i32; 7] = [2, 1, -3, -2, 0, 0, 0];
+const INDUCTANCE: [i32; 7] = [2, 1, -2, -2, 0, 0, 0];
+const FLUX_DENSITY: [i32; 7] = [0, 1, -2, -1, 0, 0, 0];
+const RATIO: [i32; 7] = [0, 0, 0, 0, 0, 0, 0];
+
+const CYCLE_UNITS: &[&str] = &["Hz", "hertz", "kHz", "kilohertz"];
+const RADIAN_UNITS: &[&str] = &["rad", "radian"];
+
+/// Known unit names, with their size in SI base units
+const UNITS: &[(&str, f64, [i32; 7])] = &[
+    ("m", 1.0, LENGTH), ("meter", 1.0, LENGTH), ("meters", 1.0, LENGTH), ("metre", 1.0, LENGTH),
+    ("km", 1e3, LENGTH), ("cm", 1e-2, LENGTH), ("centimeter", 1e-2, LENGTH),
+    ("centimeters", 1e-2, LENGTH), ("mm", 1e-3, LENGTH), ("millimeter", 1e-3, LENGTH),
+    ("millimeters", 1e-3, LENGTH), ("um", 1e-6, LENGTH), ("μm", 1e-6, LENGTH),
+    ("in", 0.0254, LENGTH), ("inch", 0.0254, LENGTH), ("inches", 0.0254, LENGTH),
+    ("ft", 0.3048, LENGTH), ("foot", 0.3048, LENGTH), ("feet", 0.3048, LENGTH),
+
+    ("L", 1e-3, VOLUME), ("l", 1e-3, VOLUME), ("liter", 1e-3, VOLUME), ("liters", 1e-3, VOLUME),
+    ("litre", 1e-3, VOLUME), ("litres", 1e-3, VOLUME), ("mL", 1e-6, VOLUME), ("ml", 1e-6, VOLUME),
+    ("milliliter", 1e-6, VOLUME),
+    ("cc", 1e-6, VOLUME),
+
+    ("kg", 1.0, MASS), ("kilogram", 1.0, MASS), ("kilograms", 1.0, MASS), ("g", 1e-3, MASS),
+    ("gram", 1e-3, MASS), ("grams", 1e-3, MASS), ("mg", 1e-6, MASS), ("milligram", 1e-6, MASS), ("lb", 0.45359237, MASS),
+    ("oz", 0.028349523125, MASS),
+
+    ("s", 1.0, TIME), ("sec", 1.0, TIME), ("second", 1.0, TIME), ("seconds", 1.0, TIME),
+    ("ms", 1e-3, TIME), ("millisecond", 1e-3, TIME), ("us", 1e-6, TIME), ("μs", 1e-6, TIME),
+
+    ("A", 1.0, CURRENT), ("ampere", 1.0, CURRENT), ("mA", 1e-3, CURRENT),
+
+    ("Hz", 1.0, FREQUENCY), ("hertz", 1.0, FREQUENCY), ("kHz", 1e3, FREQUENCY),
+    ("kilohertz", 1e3, FREQUENCY),
+
+    ("N", 1.0, FORCE), ("newton", 1.0, FORCE), ("Pa", 1.0, PRESSURE), ("pascal", 1.0, PRESSURE),
+    ("kPa", 1e3, PRESSURE), ("J", 1.0, ENERGY), ("joule", 1.0, ENERGY),
+    ("W", 1.0, POWER), ("watt", 1.0, POWER), ("watts", 1.0, POWER), ("mW", 1e-3, POWER),
+    ("V", 1.0, VOLTAGE), ("volt", 1.0, VOLTAGE), ("volts", 1.0, VOLTAGE), ("mV", 1e-3, VOLTAGE),
+    ("ohm", 1.0, RESISTANCE), ("ohms", 1.0, RESISTANCE), ("Ω", 1.0, RESISTANCE),
+    ("kohm", 1e3, RESISTANCE), ("H", 1.0, INDUCTANCE), ("henry", 1.0, INDUCTANCE),
+    ("mH", 1e-3, INDUCTANCE), ("millihenry", 1e-3, INDUCTANCE), ("uH", 1e-6, INDUCTANCE), ("μH", 1e-6, INDUCTANCE),
+    ("T", 1.0, FLUX_DENSITY), ("tesla", 1.0, FLUX_DENSITY),
+
+    ("rad", 1.0, RATIO), ("radian", 1.0, RATIO), ("dimensionless", 1.0, RATIO), ("%", 0.01, RATIO),
+];
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Name(String),
+    Int(i32),
+    Mul,
+    Div,
+    Pow,
+    Open,
+    Close,
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphabetic() || c == '%'
+}
+
+fn tokenize(expr: &str) -> Result<Vec<Token>, UnitError> {
+    let mut tokens = vec![];
+    let mut chars = expr.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if is_name_char(c) {
+            let mut name = String::new();
+            while let Some(&c) = chars.peek() {
+                if !is_name_char(c) { break; }
+                name.push(c);
+                chars.next();
+            }
+            tokens.push(Token::Name(name));
+        } else if c.is_digit(10) || c == '-' {
+            let mut int = String::new();
+            int.push(c);
+            chars.next();
+            while let Some(&c) = chars.peek() {
+                if !c.is_digit(10) { break; }
+                int.push(c);
+                chars.next();
+            }
+            let value = int.parse::<i32>().map_err(|_| UnitError::Syntax(expr.to_string()))?;
+            tokens.push(Token::Int(value));
+        } else {
+            chars.next();
+            let token = match c {
+                '*' | '·' => {
+                    if chars.peek() == Some(&'*') {
+                        chars.next();
+                        Token::Pow
+                    } else {
+                        Token::Mul
+                    }
+                },
+                '/' => Token::Div,
+                '^' => Token::Pow,
+                '(' => Token::Open,
+                ')' => Token::Close,
+                _ => return Err(UnitError::Syntax(expr.to_string())),
+            };
+            tokens.push(token);
+        }
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent parser over the unit tokens. Multiplication may be implicit, as in `tesla meter`,
+/// and an exponent may follow a unit directly, as in `cm2`
+struct Parser<'a> {
+    expr: &'a str,
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn syntax_error(&self) -> UnitError {
+        UnitError::Syntax(self.expr.to_string())
+    }
+
+    // product := factor (('*' | '/' | implicit) factor)*
+    fn product(&mut self) -> Result<ParsedUnit, UnitError> {
+        let mut unit = self.factor()?;
+        loop {
+            match self.peek().cloned() {
+                Some(Token::Mul) => {
+                    self.next();
+                    unit = unit.mul(self.factor()?);
+                },
+                Some(Token::Div) => {
+                    self.next();
+                    unit = unit.mul(self.factor()?.powi(-1));
+                },
+                Some(Token::Name(_)) | Some(Token::Open) => {
+                    unit = unit.mul(self.factor()?);
+                },
+                _ => return Ok(unit),
+            }
+        }
+    }
+
+    // factor := (name | '(' product ')') ('^' int | int)?
+    fn factor(&mut self) -> Result<ParsedUnit, UnitError> {
+        let base = match self.next() {
+            Some(Token::Name(name)) => {
+                let &(_, scale, dimension) = UNITS.iter().find(|u| u.0 == name)
+                    .ok_or_else(|| UnitError::UnknownUnit(name.clone()))?;
+                let cycles = if CYCLE_UNITS.contains(&name.as_str()) { 1 } else { 0 };
+                let radians = if RADIAN_UNITS.contains(&name.as_str()) { 1 } else { 0 };
+                ParsedUnit { scale, dimension, cycles, radians }
+            },
+            // pint writes reciprocals as `1 / second`
+            Some(Token::Int(1)) => ParsedUnit::one(),
+            Some(Token::Open) => {
+                let unit = self.product()?;
+                if self.next() != Some(Token::Close) {
+                    return Err(self.syntax_error());
+                }
+                unit
+            },
+            _ => return Err(self.syntax_error()),
+        };
+        match self.peek().cloned() {
+            Some(Token::Pow) => {
+                self.next();
+                match self.next() {
+                    Some(Token::Int(exp)) => Ok(base.powi(exp)),
+                    _ => Err(self.syntax_error()),
+                }
+            },
+            Some(Token::Int(exp)) => {
+                self.next();
+                Ok(base.powi(exp))
+            },
+            _ => Ok(base),
+        }
+    }
+}
+
+/// Parse a unit expression such as `kg / m**3` or `Pa*s/m^3`. An empty expression is unitless
+pub fn parse_unit(expr: &str) -> Result<ParsedUnit, UnitError> {
+    let tokens = tokenize(expr)?;
+    if tokens.is_empty() {
+        return Ok(ParsedUnit::one());
+    }
+    let mut parser = Parser { expr, tokens, pos: 0 };
+    let unit = parser.product()?;
+    if parser.pos != parser.tokens.len() {
+        return Err(parser.syntax_error());
+    }
+    Ok(unit)
+}
+
+/// Split text such as `35Hz` into its leading number and the remaining unit expression. The
+/// number must be finite, so `inf`, `nan` and numbers too big for an `f64` are rejected
+pub fn split_number(text: &str) -> Result<(f64, &str), UnitError> {
+    let text = text.trim();
+    let mut number = None;
+    for (index, c) in text.char_indices() {
+        let end = index + c.len_utf8();
+        if let Ok(value) = text[..end].parse::<f64>() {
+            number = Some((value, &text[end..]));
+        }
+    }
+    match number {
+        Some((value, rest)) if value.is_finite() => Ok((value, rest)),
+        _ => Err(UnitError::BadNumber(text.to_string())),
+    }
+}
+
+/// Parse text such as `1.2 cm` into SI base units, checking that it has the dimension of `unit`.
+/// A bare number is taken to be in `unit`. When both units count an angle, cycles are converted to
+/// radians or back, so `50 Hz` entered in rad/s is 314 rad/s
+pub fn parse_quantity(text: &str, unit: &DisplayUnit) -> Result<f64, UnitError> {
+    let (value, unit_expr) = split_number(text)?;
+    if unit_expr.trim().is_empty() {
+        return Ok(value * unit.scale);
+    }
+    let parsed = parse_unit(unit_expr)?;
+    if parsed.dimension != unit.dimension {
+        return Err(UnitError::Incompatible {
+            unit: unit_expr.trim().to_string(),
+            expected: unit.symbol.clone(),
+        });
+    }
+    Ok(value * parsed.scale * angle_scale(&parsed, &parse_unit(&unit.symbol)?))
+}
+
+/// Factor converting the cycles in `parsed` to the radians in `expected`, or back. 1 unless both
+/// units count an angle
+pub fn angle_scale(parsed: &ParsedUnit, expected: &ParsedUnit) -> f64 {
+    if parsed.has_angle() && expected.has_angle() {
+        (2.0 * PI).powi(parsed.cycles - expected.cycles)
+    } else {
+        1.0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use parameters::builtin_defaults;
+
+    fn nearly_equal(a: f64, b: f64) -> bool {
+        ((a - b) / b).abs() < 1e-12
+    }
+
+    #[test]
+    fn display_units_parse() {
+        let P = builtin_defaults().unwrap();
+        for param in P.param_map.values() {
+            let parsed = parse_unit(&param.unit.symbol).unwrap();
+            assert_eq!(parsed.dimension, param.unit.dimension, "{}", param.name);
+            assert!(nearly_equal(parsed.scale, param.unit.scale), "{}", param.name);
+        }
+    }
+
+    #[test]
+    fn converts_to_si() {
+        let P = builtin_defaults().unwrap();
+        assert!(nearly_equal(parse_quantity("12 mm", &P.Xmax.unit).unwrap(), 0.012));
+        assert!(nearly_equal(parse_quantity("1.2 cm", &P.Xmax.unit).unwrap(), 0.012));
+        assert!(nearly_equal(parse_quantity("3.5 L", &P.Vb.unit).unwrap(), 0.0035));
+        assert!(nearly_equal(parse_quantity("0.12 ft^3", &P.Vb.unit).unwrap(), 0.12 * 0.3048f64.powi(3)));
+        assert!(nearly_equal(parse_quantity("35Hz", &P.Fs.unit).unwrap(), 35.0));
+        assert!(nearly_equal(parse_quantity("9.1", &P.Vb.unit).unwrap(), 0.0091));
+        assert!(nearly_equal(parse_quantity("1 kg / m**3", &P.ρ0.unit).unwrap(), 1.0));
+        assert!(nearly_equal(parse_quantity("8 tesla meter", &P.Bl.unit).unwrap(), 8.0));
+        assert!(nearly_equal(parse_quantity("1 (Pa * s) / m^3", &P.Ras.unit).unwrap(), 1.0));
+    }
+
+    #[test]
+    fn rejects_incompatible() {
+        let P = builtin_defaults().unwrap();
+        assert_eq!(parse_quantity("3 L", &P.Xmax.unit), Err(UnitError::Incompatible {
+            unit: "L".to_string(),
+            expected: "mm".to_string(),
+        }));
+        assert_eq!(parse_quantity("3 furlong", &P.Xmax.unit), Err(UnitError::UnknownUnit("furlong".to_string())));
+        assert_eq!(parse_quantity("mm", &P.Xmax.unit), Err(UnitError::BadNumber("mm".to_string())));
+        for text in ["inf", "-inf mm", "nan", "infinity", "1e999 mm"].iter() {
+            assert!(match parse_quantity(text, &P.Xmax.unit) {
+                Err(UnitError::BadNumber(_)) => true,
+                _ => false,
+            }, "{}", text);
+        }
+    }
+
+    #[test]
+    fn converts_cycles_to_radians() {
+        let P = builtin_defaults().unwrap();
+        assert!(nearly_equal(parse_quantity("50 Hz", &P.ωs.unit).unwrap(), 100.0 * PI));
+        assert!(nearly_equal(parse_quantity("50 rad/s", &P.ωs.unit).unwrap(), 50.0));
+        assert!(nearly_equal(parse_quantity("100 rad/s", &P.Fs.unit).unwrap(), 50.0 / PI));
+        assert!(nearly_equal(parse_quantity("0.05 kHz", &P.Fs.unit).unwrap(), 50.0));
+        assert!(nearly_equal(parse_quantity("50", &P.ωs.unit).unwrap(), 50.0));
+    }
+}
@@ -0,0 +1,191 @@
+//! Physical quantities used by the parameters, and the units they are displayed in
+//!
+//! Parameter values are stored in SI base units. Quantities that uom has no name for, such as
+//! acoustic mass and compliance, are defined here by their base dimensions
+
+use std::marker::PhantomData;
+use uom::si::{Dimension, Quantity, SI};
+use uom::typenum::Integer;
+
+use uom::si::{area, frequency, length, mass, mass_density, time, velocity, volume};
+
+pub use uom::si::f64::{Area, Frequency, Length, Mass, MassDensity, Time, Velocity, Volume};
+
+/// Quantity of dimension `D`, stored as an `f64` in SI base units
+pub type Q<D> = Quantity<D, SI<f64>, f64>;
+
+/// Dimensions of the parameter quantities, as exponents of (L, M, T, I, Th, N, J)
+pub mod dim {
+    use uom::si::ISQ;
+    use uom::typenum::{N1, N2, N3, N4, P1, P2, P3, P4, Z0};
+
+    pub type Ratio = ISQ<Z0, Z0, Z0, Z0, Z0, Z0, Z0>;
+    pub type Length = ISQ<P1, Z0, Z0, Z0, Z0, Z0, Z0>;
+    pub type Area = ISQ<P2, Z0, Z0, Z0, Z0, Z0, Z0>;
+    pub type Volume = ISQ<P3, Z0, Z0, Z0, Z0, Z0, Z0>;
+    pub type Mass = ISQ<Z0, P1, Z0, Z0, Z0, Z0, Z0>;
+    pub type Time = ISQ<Z0, Z0, P1, Z0, Z0, Z0, Z0>;
+    pub type Frequency = ISQ<Z0, Z0, N1, Z0, Z0, Z0, Z0>;
+    pub type Velocity = ISQ<P1, Z0, N1, Z0, Z0, Z0, Z0>;
+    pub type MassDensity = ISQ<N3, P1, Z0, Z0, Z0, Z0, Z0>;
+    /// kg / m^4
+    pub type AcousticMass = ISQ<N4, P1, Z0, Z0, Z0, Z0, Z0>;
+    /// m^5 / N
+    pub type AcousticCompliance = ISQ<P4, N1, P2, Z0, Z0, Z0, Z0>;
+    /// Pa * s / m^3
+    pub type AcousticResistance = ISQ<N4, P1, N1, Z0, Z0, Z0, Z0>;
+    /// m / N
+    pub type MechanicalCompliance = ISQ<Z0, N1, P2, Z0, Z0, Z0, Z0>;
+    /// N * s / m
+    pub type MechanicalResistance = ISQ<Z0, P1, N1, Z0, Z0, Z0, Z0>;
+    /// ohm
+    pub type Resistance = ISQ<P2, P1, N3, N2, Z0, Z0, Z0>;
+    /// tesla * m
+    pub type ForceFactor = ISQ<P1, P1, N2, N1, Z0, Z0, Z0>;
+    /// V
+    pub type Voltage = ISQ<P2, P1, N3, N1, Z0, Z0, Z0>;
+    /// W
+    pub type Power = ISQ<P2, P1, N3, Z0, Z0, Z0, Z0>;
+    /// H
+    pub type Inductance = ISQ<P2, P1, N2, N2, Z0, Z0, Z0>;
+}
+
+pub type Ratio = Q<dim::Ratio>;
+pub type AcousticMass = Q<dim::AcousticMass>;
+pub type AcousticCompliance = Q<dim::AcousticCompliance>;
+pub type AcousticResistance = Q<dim::AcousticResistance>;
+pub type MechanicalCompliance = Q<dim::MechanicalCompliance>;
+pub type MechanicalResistance = Q<dim::MechanicalResistance>;
+pub type Resistance = Q<dim::Resistance>;
+pub type ForceFactor = Q<dim::ForceFactor>;
+pub type Voltage = Q<dim::Voltage>;
+pub type Power = Q<dim::Power>;
+pub type Inductance = Q<dim::Inductance>;
+
+/// Exponents of the SI base dimensions (L, M, T, I, Th, N, J) of `D`
+pub fn dimension<D: Dimension + ?Sized>() -> [i32; 7] {
+    [D::L::to_i32(), D::M::to_i32(), D::T::to_i32(), D::I::to_i32(),
+     D::Th::to_i32(), D::N::to_i32(), D::J::to_i32()]
+}
+
+/// Quantity of dimension `D` from a value in SI base units
+pub fn si<D: Dimension + ?Sized>(value: f64) -> Q<D> {
+    Quantity { dimension: PhantomData, units: PhantomData, value }
+}
+
+/// Unit a parameter of dimension `D` is displayed and entered in
+pub struct Unit<D: Dimension + ?Sized> {
+    pub symbol: &'static str,
+    pub one: Q<D>,
+}
+
+/// A `Unit` with its dimension erased, so parameters of different dimensions can be stored together
+#[derive(Clone, Debug, PartialEq)]
+pub struct DisplayUnit {
+    pub symbol: String,
+    /// Size of one display unit in SI base units
+    pub scale: f64,
+    pub dimension: [i32; 7],
+}
+
+impl<D: Dimension + ?Sized> Unit<D> {
+    pub fn new(symbol: &'static str, one: Q<D>) -> Unit<D> {
+        Unit { symbol, one }
+    }
+
+    pub fn display(&self) -> DisplayUnit {
+        DisplayUnit {
+            symbol: self.symbol.to_string(),
+            scale: self.one.value,
+            dimension: dimension::<D>(),
+        }
+    }
+}
+
+pub fn unitless() -> Unit<dim::Ratio> {
+    Unit::new("", si(1.0))
+}
+
+pub fn kg_per_m3() -> Unit<dim::MassDensity> {
+    Unit::new("kg/m^3", MassDensity::new::<mass_density::kilogram_per_cubic_meter>(1.0))
+}
+
+pub fn m_per_s() -> Unit<dim::Velocity> {
+    Unit::new("m/s", Velocity::new::<velocity::meter_per_second>(1.0))
+}
+
+pub fn m() -> Unit<dim::Length> {
+    Unit::new("m", Length::new::<length::meter>(1.0))
+}
+
+pub fn mm() -> Unit<dim::Length> {
+    Unit::new("mm", Length::new::<length::millimeter>(1.0))
+}
+
+pub fn cm() -> Unit<dim::Length> {
+    Unit::new("cm", Length::new::<length::centimeter>(1.0))
+}
+
+pub fn cm2() -> Unit<dim::Area> {
+    Unit::new("cm^2", Area::new::<area::square_centimeter>(1.0))
+}
+
+pub fn liter() -> Unit<dim::Volume> {
+    Unit::new("L", Volume::new::<volume::liter>(1.0))
+}
+
+pub fn g() -> Unit<dim::Mass> {
+    Unit::new("g", Mass::new::<mass::gram>(1.0))
+}
+
+pub fn s() -> Unit<dim::Time> {
+    Unit::new("s", Time::new::<time::second>(1.0))
+}
+
+pub fn hz() -> Unit<dim::Frequency> {
+    Unit::new("Hz", Frequency::new::<frequency::hertz>(1.0))
+}
+
+pub fn rad_per_s() -> Unit<dim::Frequency> {
+    Unit::new("rad/s", si(1.0))
+}
+
+pub fn ohm() -> Unit<dim::Resistance> {
+    Unit::new("ohm", si(1.0))
+}
+
+pub fn tesla_m() -> Unit<dim::ForceFactor> {
+    Unit::new("T*m", si(1.0))
+}
+
+pub fn kg_per_m4() -> Unit<dim::AcousticMass> {
+    Unit::new("kg/m^4", si(1.0))
+}
+
+pub fn cm5_per_n() -> Unit<dim::AcousticCompliance> {
+    Unit::new("cm^5/N", si(1e-10))
+}
+
+pub fn pa_s_per_m3() -> Unit<dim::AcousticResistance> {
+    Unit::new("Pa*s/m^3", si(1.0))
+}
+
+pub fn mm_per_n() -> Unit<dim::MechanicalCompliance> {
+    Unit::new("mm/N", si(1e-3))
+}
+
+pub fn n_s_per_m() -> Unit<dim::MechanicalResistance> {
+    Unit::new("N*s/m", si(1.0))
+}
+
+pub fn volt() -> Unit<dim::Voltage> {
+    Unit::new("V", si(1.0))
+}
+
+pub fn watt() -> Unit<dim::Power> {
+    Unit::new("W", si(1.0))
+}
+
+pub fn mh() -> Unit<dim::Inductance> {
+    Unit::new("mH", si(1e-3))
+}